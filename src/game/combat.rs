@@ -1,5 +1,11 @@
-use crate::game::{player::Player, world::{World, Npc}};
-use std::io::{self, Write};
+use crate::game::{
+    player::Player,
+    rng::Rng,
+    skills::{Skill, SkillEffect},
+    world::{Npc, NpcId, World},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 pub enum CombatAction {
     Attack,
@@ -8,180 +14,660 @@ pub enum CombatAction {
     Retreat,
 }
 
+// odds for everything random in a fight, see rng.rs
+const PLAYER_HIT_CHANCE: f32 = 0.85;
+const NPC_HIT_CHANCE: f32 = 0.8;
+const CRIT_CHANCE: f32 = 0.1;
+const CRIT_MULTIPLIER: f32 = 2.0;
+const DAMAGE_VARIANCE: f32 = 0.2; // hits land anywhere from 80% to 120% of the base damage
+const FLEE_CHANCE: f32 = 0.6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CombatState {
     PlayerTurn,
-    NPCTurn,
+    ChoosingFood, // player picked eat and we're waiting on which item
+    ChoosingSkill, // same for skills
+    ChoosingTarget, // picked an attack or skill and there's more than one enemy to aim it at
+    NPCTurn,      // working through the rest of the round's turn order
     Defeat,
     Victory,
     Fled,
 }
 
+// someone who gets a turn each round
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Combatant {
+    Player,
+    Enemy(NpcId),
+}
+
+// what the player picked before being asked who to aim it at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Pending {
+    Attack,
+    Skill(String),
+}
+
+/* Combat is a state machine that lives in the session (see Mode::Combat) instead of a loop.
+ * Every line the player types while fighting goes through handle_input, which plays out
+ * that round and returns the text for it. Nothing blocks, so a fight can be driven one
+ * input at a time from the terminal or a browser, and saved halfway through.
+ *
+ * A fight can have any number of enemies. Every round everyone still standing rolls for turn
+ * order, and the round plays out in that order: enemies act on their own, and the fight stops to
+ * ask the player when it's their turn. The fight is won once every enemy is down.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Combat {
     pub state: CombatState,
-    pub npc_index: usize,
+    pub enemies: Vec<NpcId>, // everyone on the other side, including the ones already beaten
     pub turn_count: u32,
+    pub order: Vec<Combatant>, // this round's turn order
+    pub next: usize,           // position in the order of whoever goes next
+    pub pending: Option<Pending>,
+    pub cooldowns: HashMap<String, u32>, // skill name -> rounds until it can be used again
+    pub stunned: HashMap<NpcId, u32>,    // enemy -> turns left to skip
+}
+
+// what can be typed on the player's turn, for help
+pub const HELP: &[(&str, &str)] = &[
+    ("a, attack", "Attack an enemy."),
+    ("e, eat", "Eat something you're carrying to heal."),
+    ("s, skill", "Use one of your skills."),
+    ("r, retreat", "Try to run away."),
+    ("<number>", "Pick from a list (food, skills, targets), or 'cancel' to go back."),
+];
+
+impl CombatAction {
+    fn parse(input: &str) -> Option<CombatAction> {
+        match input.trim().to_lowercase().as_str() {
+            "a" | "attack" => Some(CombatAction::Attack),
+            "e" | "eat" => Some(CombatAction::Eat),
+            "s" | "skill" => Some(CombatAction::Skill),
+            "r" | "retreat" => Some(CombatAction::Retreat),
+            _ => None,
+        }
+    }
 }
 
 impl Combat {
-    pub fn new(npc_index: usize) -> Self {
+    pub fn new(enemies: Vec<NpcId>) -> Self {
         Combat {
-            state: CombatState::PlayerTurn,
-            npc_index,
+            state: CombatState::NPCTurn,
+            enemies,
             turn_count: 0,
+            order: Vec::new(),
+            next: 0,
+            pending: None,
+            cooldowns: HashMap::new(),
+            stunned: HashMap::new(),
         }
     }
 
-    pub fn start_combat(player: &mut Player, world: &mut World, npc_index: usize) -> String {
-        let npc = &world.npcs[npc_index];
-        println!("\n=== COMBAT INITIATED ===");
-        println!("You are fighting: {}", npc.name);
-        println!("Enemy HP: {} | Enemy AP: {}", npc.health_points, npc.attack_power);
-        println!("Your HP: {} | Your AP: {}", player.health_points, player.attack_power);
-        println!("========================\n");
+    // Sets up a fight and returns it along with the intro text. Enemies that win the first
+    // turn order roll get to act right away, then the player is prompted.
+    pub fn start_combat(
+        player: &mut Player,
+        world: &mut World,
+        enemies: Vec<NpcId>,
+        rng: &mut Rng,
+    ) -> (Combat, String) {
+        let mut combat = Combat::new(enemies);
+
+        let mut out = vec![
+            "=== COMBAT INITIATED ===".to_string(),
+            format!("You are fighting: {}", combat.enemy_names(world).join(", ")),
+        ];
+        for npc in combat.living(world) {
+            out.push(format!(
+                "  {} - HP: {} | AP: {}",
+                npc.name, npc.health_points, npc.attack_power
+            ));
+        }
+        out.push(format!(
+            "Your HP: {} | Your AP: {} | Your DEF: {}",
+            player.health_points,
+            player.attack(),
+            player.defense()
+        ));
+        out.push("========================\n".to_string());
 
-        let mut combat = Combat::new(npc_index);
-        combat.combat_loop(player, world)
+        combat.new_round(world, rng, &mut out);
+        combat.advance(player, world, rng, &mut out);
+        (combat, out.join("\n"))
     }
 
-    fn combat_loop(&mut self, player: &mut Player, world: &mut World) -> String {
-        loop {
-            match self.state {
-                CombatState::PlayerTurn => {
-                    let action = self.get_player_action(player, world);
-                    match action {
-                        CombatAction::Attack => {
-                            let damage = player.attack_power;
-                            world.npcs[self.npc_index].health_points -= damage;
-                            println!("You attack for {} damage!", damage);
-                            
-                            if world.npcs[self.npc_index].health_points <= 0 {
-                                self.state = CombatState::Victory;
-                                continue;
-                            }
-                        }
-                        CombatAction::Eat => {
-                            // See get_player_action
-                        }
-                        CombatAction::Retreat => {
-                            self.state = CombatState::Fled;
-                            continue;
-                        }
-                        CombatAction::Skill => {
-                            println!("You don't know any skills yet!");// Need to add skills and experience later
-                            continue; 
-                        }
+    // one of the npcs we're fighting. combat only ever starts against npcs that exist
+    fn enemy<'a>(&self, world: &'a World, id: &NpcId) -> &'a Npc {
+        world.npc(id).expect("combat against an npc that isn't in the world")
+    }
+
+    fn enemy_mut<'a>(&self, world: &'a mut World, id: &NpcId) -> &'a mut Npc {
+        world.npc_mut(id).expect("combat against an npc that isn't in the world")
+    }
+
+    // enemies still standing, in the order the fight started with them
+    pub fn living<'a>(&self, world: &'a World) -> Vec<&'a Npc> {
+        self.enemies
+            .iter()
+            .map(|id| self.enemy(world, id))
+            .filter(|npc| !npc.dead)
+            .collect()
+    }
+
+    fn enemy_names(&self, world: &World) -> Vec<String> {
+        self.living(world).iter().map(|npc| npc.name.clone()).collect()
+    }
+
+    // true once the fight has been won, lost or run away from
+    pub fn is_over(&self) -> bool {
+        matches!(
+            self.state,
+            CombatState::Victory | CombatState::Defeat | CombatState::Fled
+        )
+    }
+
+    // what the player is being asked right now
+    pub fn prompt(&self, player: &Player, world: &World) -> String {
+        match self.state {
+            CombatState::ChoosingFood => {
+                let mut lines = vec!["Edible items:".to_string()];
+                for (i, item) in player.inventory.iter().filter(|item| item.can_eat).enumerate() {
+                    // no telling what something does before it's been eaten once
+                    if player.identified.contains(&item.name) {
+                        lines.push(format!("  {}: {} (heals {})", i + 1, item.name, item.heal_amount));
+                    } else {
+                        lines.push(format!("  {}: {} (heals ?)", i + 1, item.name));
                     }
-                    self.state = CombatState::NPCTurn;
                 }
-                CombatState::NPCTurn => {
-                    let npc = &world.npcs[self.npc_index];
-                    let damage = npc.attack_power;
-                    player.health_points -= damage;
-                    println!("{} attacks you for {} damage!", npc.name, damage);
-                    
-                    if player.health_points <= 0 {
-                        self.state = CombatState::Defeat;
-                        continue;
-                    }
-                    
-                    self.state = CombatState::PlayerTurn;
-                    self.turn_count += 1;
+                lines.push("Choose item to eat (number) or 'cancel'.".to_string());
+                lines.join("\n")
+            }
+            CombatState::ChoosingSkill => {
+                let mut lines = vec!["Skills:".to_string()];
+                for (i, name) in player.skills.iter().enumerate() {
+                    let ready = match self.cooldowns.get(name) {
+                        Some(&rounds) if rounds > 0 => rounds_left(rounds),
+                        _ => "ready".to_string(),
+                    };
+                    lines.push(format!("  {}: {} ({})", i + 1, name, ready));
                 }
-                CombatState::Victory => {
-                    println!("\n=== VICTORY! ===");
-                    println!("You defeated the {}!", world.npcs[self.npc_index].name);
-                    
-                    // Remove defeated NPC from the world
-                    world.npcs.remove(self.npc_index);
-                    
-                    return "You emerge victorious from combat!".to_string();
+                lines.push("Choose skill to use (number) or 'cancel'.".to_string());
+                lines.join("\n")
+            }
+            CombatState::ChoosingTarget => {
+                let mut lines = vec!["Targets:".to_string()];
+                for (i, npc) in self.living(world).iter().enumerate() {
+                    lines.push(format!("  {}: {} (HP {})", i + 1, npc.name, npc.health_points));
                 }
-                CombatState::Defeat => {
-                    println!("\n=== DEFEAT ===");
-                    println!("You have been defeated...");
-                    return "GAME OVER - You died in combat!".to_string();
+                lines.push("Choose target (number) or 'cancel'.".to_string());
+                lines.join("\n")
+            }
+            _ => "Choose action: [A]ttack, [E]at, [S]kill, [R]etreat".to_string(),
+        }
+    }
+
+    // Feeds one line of player input into the fight and returns what happened
+    pub fn handle_input(
+        &mut self,
+        input: &str,
+        player: &mut Player,
+        world: &mut World,
+        rng: &mut Rng,
+    ) -> String {
+        let mut out = Vec::new();
+
+        match self.state {
+            CombatState::PlayerTurn => match CombatAction::parse(input) {
+                Some(action) => self.player_action(action, player, world, rng, &mut out),
+                None => out.push("Invalid action! Please choose A, E, S, or R.".to_string()),
+            },
+            CombatState::ChoosingFood => self.choose_food(input, player, &mut out),
+            CombatState::ChoosingSkill => self.choose_skill(input, player, world, rng, &mut out),
+            CombatState::ChoosingTarget => self.choose_target(input, player, world, rng, &mut out),
+            _ => {}
+        }
+
+        self.advance(player, world, rng, &mut out);
+        out.join("\n")
+    }
+
+    // the player has done their thing, carry on with the round
+    fn end_player_turn(&mut self) {
+        self.next += 1;
+        self.state = CombatState::NPCTurn;
+    }
+
+    fn player_action(
+        &mut self,
+        action: CombatAction,
+        player: &mut Player,
+        world: &mut World,
+        rng: &mut Rng,
+        out: &mut Vec<String>,
+    ) {
+        match action {
+            CombatAction::Attack => self.aim(Pending::Attack, player, world, rng, out),
+            CombatAction::Eat => {
+                if player.inventory.iter().any(|item| item.can_eat) {
+                    self.state = CombatState::ChoosingFood;
+                } else {
+                    out.push("You have no edible items!".to_string());
                 }
-                CombatState::Fled => {
-                    println!("\n=== RETREAT ===");
-                    println!("You successfully fled from combat!");
-                    return "You escaped from the fight.".to_string();
+            }
+            CombatAction::Retreat => {
+                if rng.chance(FLEE_CHANCE) {
+                    self.state = CombatState::Fled;
+                } else {
+                    out.push("You try to run, but you're cut off!".to_string());
+                    self.end_player_turn();
+                }
+            }
+            CombatAction::Skill => {
+                if player.skills.is_empty() {
+                    out.push("You don't know any skills yet!".to_string());
+                } else {
+                    self.state = CombatState::ChoosingSkill;
                 }
             }
-            
-            // Show status after each round
-            let npc = &world.npcs[self.npc_index];
-            println!("\n--- Status ---");
-            println!("Your HP: {} | Enemy HP: {}", player.health_points, npc.health_points);
-            println!("--------------\n");
         }
     }
 
-    fn get_player_action(&self, player: &mut Player, world: &World) -> CombatAction {
-        loop {
-            print!("Choose action: [A]ttack, [E]at, [S]kill, [R]etreat > ");
-            io::stdout().flush().unwrap();
-            
-            let mut input = String::new();
-            io::stdin().read_line(&mut input).unwrap();
-            let input = input.trim().to_lowercase();
-            
-            match input.as_str() {
-                "a" | "attack" => return CombatAction::Attack,
-                "e" | "eat" => {
-                    if self.handle_eat_action(player) {
-                        return CombatAction::Eat;
+    // with one enemy left there's nobody to choose between, otherwise ask
+    fn aim(
+        &mut self,
+        action: Pending,
+        player: &mut Player,
+        world: &mut World,
+        rng: &mut Rng,
+        out: &mut Vec<String>,
+    ) {
+        let living: Vec<NpcId> = self.living(world).iter().map(|npc| npc.id.clone()).collect();
+        if let [target] = living.as_slice() {
+            self.resolve(action, target, player, world, rng, out);
+        } else {
+            self.pending = Some(action);
+            self.state = CombatState::ChoosingTarget;
+        }
+    }
+
+    fn choose_target(
+        &mut self,
+        input: &str,
+        player: &mut Player,
+        world: &mut World,
+        rng: &mut Rng,
+        out: &mut Vec<String>,
+    ) {
+        let input = input.trim();
+        // backing out of the target list goes back to the action prompt like the other lists
+        self.state = CombatState::PlayerTurn;
+        let Some(action) = self.pending.take() else {
+            return;
+        };
+
+        if input == "cancel" {
+            return;
+        }
+
+        let target = input
+            .parse::<usize>()
+            .ok()
+            .and_then(|choice| self.living(world).get(choice.wrapping_sub(1)).map(|npc| npc.id.clone()));
+        match target {
+            Some(target) => self.resolve(action, &target, player, world, rng, out),
+            None => out.push("Invalid choice!".to_string()),
+        }
+    }
+
+    // carries out an attack or skill against the chosen enemy
+    fn resolve(
+        &mut self,
+        action: Pending,
+        target: &NpcId,
+        player: &mut Player,
+        world: &mut World,
+        rng: &mut Rng,
+        out: &mut Vec<String>,
+    ) {
+        match action {
+            Pending::Attack => {
+                let name = self.enemy(world, target).name.clone();
+                if !rng.chance(PLAYER_HIT_CHANCE) {
+                    out.push(format!("You attack the {}, but miss!", name));
+                } else {
+                    let mut damage = roll_damage(player.attack(), rng);
+                    if rng.chance(CRIT_CHANCE) {
+                        damage = (damage as f32 * CRIT_MULTIPLIER).round() as i32;
+                        out.push("Critical hit!".to_string());
                     }
-                    
+                    out.push(format!("You attack the {} for {} damage!", name, damage));
+                    self.damage_enemy(target, damage, player, world, out);
+                }
+                self.end_player_turn();
+            }
+            Pending::Skill(name) => {
+                if let Some(skill) = world.skills.iter().find(|s| s.name == name).cloned() {
+                    self.use_skill(&skill, Some(target), player, world, rng, out);
                 }
-                "s" | "skill" => return CombatAction::Skill,
-                "r" | "retreat" => return CombatAction::Retreat,
-                _ => println!("Invalid action! Please choose A, E, S, or R."),
             }
         }
     }
 
-    fn handle_eat_action(&self, player: &mut Player) -> bool {
-        let edible_items: Vec<_> = player.inventory.iter()
-            .filter(|item| item.can_eat)
-            .collect();
-        
-        if edible_items.is_empty() {
-            println!("You have no edible items!");
-            return false;
-        }
-        
-        println!("Edible items:");
-        for (i, item) in edible_items.iter().enumerate() {
-            println!("  {}: {} (heals {})", i + 1, item.name, item.heal_amount);
-        }
-        
-        print!("Choose item to eat (number) or 'cancel': ");
-        io::stdout().flush().unwrap();
-        
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
+    fn choose_food(&mut self, input: &str, player: &mut Player, out: &mut Vec<String>) {
         let input = input.trim();
-        
+        // anything but a valid choice puts us back at the action prompt without using the turn
+        self.state = CombatState::PlayerTurn;
+
         if input == "cancel" {
-            return false;
+            return;
         }
-        
+
+        let edible_items: Vec<_> = player.inventory.iter().filter(|item| item.can_eat).collect();
         if let Ok(choice) = input.parse::<usize>() {
             if choice > 0 && choice <= edible_items.len() {
                 let item_name = edible_items[choice - 1].name.clone();
-                let heal_amount = edible_items[choice - 1].heal_amount;
-                
-                player.health_points += heal_amount;
-                player.remove_item(&item_name);
-                
-                println!("You ate {} and {} {} HP!", item_name, 
-                    if heal_amount >= 0 { "gained" } else { "lost" }, 
-                    heal_amount.abs());
-                return true;
+                let heal_amount = player.eat_item(&item_name).unwrap_or_default();
+
+                out.push(format!(
+                    "You ate {} and {} {} HP!",
+                    item_name,
+                    if heal_amount >= 0 { "gained" } else { "lost" },
+                    heal_amount.abs()
+                ));
+                self.end_player_turn();
+                return;
+            }
+        }
+
+        out.push("Invalid choice!".to_string());
+    }
+
+    fn choose_skill(
+        &mut self,
+        input: &str,
+        player: &mut Player,
+        world: &mut World,
+        rng: &mut Rng,
+        out: &mut Vec<String>,
+    ) {
+        let input = input.trim();
+        // same as food, anything but a usable skill goes back to the action prompt
+        self.state = CombatState::PlayerTurn;
+
+        if input == "cancel" {
+            return;
+        }
+
+        let skill = input
+            .parse::<usize>()
+            .ok()
+            .and_then(|choice| player.skills.get(choice.wrapping_sub(1)))
+            .and_then(|name| world.skills.iter().find(|s| &s.name == name))
+            .cloned();
+        let Some(skill) = skill else {
+            out.push("Invalid choice!".to_string());
+            return;
+        };
+
+        if let Some(&rounds) = self.cooldowns.get(&skill.name).filter(|&&r| r > 0) {
+            out.push(format!("{} isn't ready yet ({}).", skill.name, rounds_left(rounds)));
+            return;
+        }
+
+        // heals are on the player, everything else needs someone to hit
+        match skill.effect {
+            SkillEffect::Heal(_) => self.use_skill(&skill, None, player, world, rng, out),
+            _ => self.aim(Pending::Skill(skill.name), player, world, rng, out),
+        }
+    }
+
+    // skills always land, but damage still varies like a normal hit
+    fn use_skill(
+        &mut self,
+        skill: &Skill,
+        target: Option<&NpcId>,
+        player: &mut Player,
+        world: &mut World,
+        rng: &mut Rng,
+        out: &mut Vec<String>,
+    ) {
+        // +1 because the round it's used in counts down too
+        if skill.cooldown > 0 {
+            self.cooldowns.insert(skill.name.clone(), skill.cooldown + 1);
+        }
+
+        match (skill.effect, target) {
+            (SkillEffect::Damage(multiplier), Some(target)) => {
+                let damage = roll_damage((player.attack() as f32 * multiplier).round() as i32, rng);
+                let name = &self.enemy(world, target).name;
+                out.push(format!("You use {} on the {} for {} damage!", skill.name, name, damage));
+                self.damage_enemy(target, damage, player, world, out);
+            }
+            (SkillEffect::Stun(turns), Some(target)) => {
+                *self.stunned.entry(target.clone()).or_default() += turns;
+                let name = &self.enemy(world, target).name;
+                out.push(format!("You use {}! The {} is stunned.", skill.name, name));
+            }
+            (SkillEffect::Heal(amount), _) => {
+                player.health_points += amount;
+                out.push(format!("You use {} and recover {} HP!", skill.name, amount));
+            }
+            _ => {}
+        }
+        self.end_player_turn();
+    }
+
+    fn damage_enemy(
+        &mut self,
+        id: &NpcId,
+        damage: i32,
+        player: &mut Player,
+        world: &mut World,
+        out: &mut Vec<String>,
+    ) {
+        let npc = self.enemy_mut(world, id);
+        npc.health_points -= damage;
+        if npc.health_points <= 0 {
+            self.defeat_enemy(id, player, world, out);
+        }
+    }
+
+    fn defeat_enemy(&self, id: &NpcId, player: &mut Player, world: &mut World, out: &mut Vec<String>) {
+        // defeated npcs are marked dead rather than removed, so ids held elsewhere stay valid
+        let npc = self.enemy_mut(world, id);
+        npc.dead = true;
+        out.push(format!("You defeated the {}!", npc.name));
+        let xp = npc.xp_reward;
+        let drops = npc.drop_everything();
+        if !drops.is_empty() {
+            let names: Vec<_> = drops.iter().map(|i| i.name.as_str()).collect();
+            out.push(format!("The {} dropped: {}", npc.name, names.join(", ")));
+            world.room_mut(&player.current_room).items.extend(drops);
+        }
+        out.extend(player.gain_xp(xp, &world.skills));
+    }
+
+    // everyone still standing rolls for a place in this round's turn order
+    fn new_round(&mut self, world: &World, rng: &mut Rng, out: &mut Vec<String>) {
+        let mut rolls = vec![(rng.next_f32(), Combatant::Player)];
+        for npc in self.living(world) {
+            rolls.push((rng.next_f32(), Combatant::Enemy(npc.id.clone())));
+        }
+        rolls.sort_by(|a, b| b.0.total_cmp(&a.0));
+        self.order = rolls.into_iter().map(|(_, combatant)| combatant).collect();
+        self.next = 0;
+
+        let names: Vec<_> = self
+            .order
+            .iter()
+            .map(|combatant| match combatant {
+                Combatant::Player => "You".to_string(),
+                Combatant::Enemy(id) => self.enemy(world, id).name.clone(),
+            })
+            .collect();
+        out.push(format!("Turn order: {}", names.join(", ")));
+    }
+
+    fn end_round(&mut self, player: &Player, world: &World, rng: &mut Rng, out: &mut Vec<String>) {
+        self.turn_count += 1;
+        for rounds in self.cooldowns.values_mut() {
+            *rounds = rounds.saturating_sub(1);
+        }
+
+        // Show status after each round
+        let enemies: Vec<_> = self
+            .living(world)
+            .iter()
+            .map(|npc| format!("{} HP: {}", npc.name, npc.health_points))
+            .collect();
+        out.push(format!(
+            "\n--- Status ---\n\
+            Your HP: {} | {}\n\
+            --------------\n",
+            player.health_points,
+            enemies.join(" | ")
+        ));
+
+        self.new_round(world, rng, out);
+    }
+
+    fn enemy_turn(&mut self, id: &NpcId, player: &mut Player, world: &World, rng: &mut Rng, out: &mut Vec<String>) {
+        let npc = self.enemy(world, id);
+        // beaten earlier in the round
+        if npc.dead {
+            return;
+        }
+
+        if let Some(turns) = self.stunned.get_mut(id).filter(|turns| **turns > 0) {
+            *turns -= 1;
+            out.push(format!("{} is stunned and can't attack!", npc.name));
+        } else if !rng.chance(NPC_HIT_CHANCE) {
+            out.push(format!("{} attacks, but misses!", npc.name));
+        } else {
+            // armor soaks up part of the hit, but something always gets through
+            let damage = (roll_damage(npc.attack_power, rng) - player.defense()).max(1);
+            player.health_points -= damage;
+            out.push(format!("{} attacks you for {} damage!", npc.name, damage));
+        }
+    }
+
+    // runs everything that doesn't need player input (enemy turns, the end of the fight)
+    // until we either need input again or the fight is over
+    fn advance(&mut self, player: &mut Player, world: &mut World, rng: &mut Rng, out: &mut Vec<String>) {
+        loop {
+            match self.state {
+                CombatState::NPCTurn => {
+                    if self.living(world).is_empty() {
+                        self.state = CombatState::Victory;
+                        continue;
+                    }
+
+                    match self.order.get(self.next).cloned() {
+                        None => self.end_round(player, world, rng, out),
+                        Some(Combatant::Player) => self.state = CombatState::PlayerTurn,
+                        Some(Combatant::Enemy(id)) => {
+                            self.next += 1;
+                            self.enemy_turn(&id, player, world, rng, out);
+                            if player.health_points <= 0 {
+                                self.state = CombatState::Defeat;
+                            }
+                        }
+                    }
+                }
+                CombatState::PlayerTurn
+                | CombatState::ChoosingFood
+                | CombatState::ChoosingSkill
+                | CombatState::ChoosingTarget => {
+                    out.push(self.prompt(player, world));
+                    return;
+                }
+                CombatState::Victory => {
+                    out.push("\n=== VICTORY! ===".to_string());
+                    out.push("You emerge victorious from combat!".to_string());
+                    return;
+                }
+                CombatState::Defeat => {
+                    out.push("\n=== DEFEAT ===".to_string());
+                    out.push("You have been defeated...".to_string());
+                    out.push("You died in combat!".to_string());
+                    return;
+                }
+                CombatState::Fled => {
+                    out.push("\n=== RETREAT ===".to_string());
+                    out.push("You successfully fled from combat!".to_string());
+                    out.push("You escaped from the fight.".to_string());
+                    return;
+                }
             }
         }
-        
-        println!("Invalid choice!");
-        false
     }
-}
\ No newline at end of file
+}
+
+// base damage give or take DAMAGE_VARIANCE, never less than 1
+fn roll_damage(base: i32, rng: &mut Rng) -> i32 {
+    let roll = rng.range(1.0 - DAMAGE_VARIANCE, 1.0 + DAMAGE_VARIANCE);
+    ((base as f32 * roll).round() as i32).max(1)
+}
+
+fn rounds_left(rounds: u32) -> String {
+    if rounds == 1 {
+        "1 round".to_string()
+    } else {
+        format!("{} rounds", rounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{
+        save,
+        testing::{new_session, run, run_script},
+    };
+
+    // one tough but harmless enemy, so a fight lasts long enough to have plenty of rolls in it
+    const WORLD: &str = r#"
+        start_room = "arena"
+
+        [[rooms]]
+        id = "arena"
+        name = "Arena"
+        description = "Sand and not much else."
+
+        [[npcs]]
+        id = "golem"
+        name = "Golem"
+        room = "arena"
+        health_points = 500
+        attack_power = 3
+        hostile = true
+    "#;
+
+    const SCRIPT: &[&str] = &["fight golem", "a", "a", "s", "1", "a", "a", "a", "r", "fight golem", "a", "a"];
+
+    #[test]
+    fn same_seed_same_fight() {
+        let first = run_script(WORLD, 42, SCRIPT);
+        let second = run_script(WORLD, 42, SCRIPT);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn different_seed_different_fight() {
+        assert_ne!(run_script(WORLD, 1, SCRIPT), run_script(WORLD, 2, SCRIPT));
+    }
+
+    #[test]
+    fn loading_mid_fight_keeps_the_rolls() {
+        let (before, after) = SCRIPT.split_at(4);
+        let mut session = new_session(WORLD, 7);
+        run(&mut session, before);
+        assert!(session.in_combat(), "the save is meant to be taken mid fight");
+        let saved = save::to_string(&session).unwrap();
+
+        let carried_on = run(&mut session, after);
+        let mut loaded = save::from_str(&saved).unwrap();
+        assert!(loaded.in_combat());
+        assert_eq!(run(&mut loaded, after), carried_on);
+    }
+}
@@ -1,258 +1,1177 @@
 use super::{
-    player::{self, Player},
-    world::World,
-    combat::Combat,
+    combat::{self, Combat},
+    containers,
+    dialogue::{self, Conversation},
+    examine::{self, Thing},
+    map,
+    parser::{self, Input, Lookup},
+    player::{MoveError, Player},
+    quests,
+    registry::{Command, CommandRegistry},
+    save,
+    death,
+    session::{GameEvent, Mode, Session},
+    triggers,
+    world::{Container, Item, Npc, NpcId, RoomId, World},
 };
 
-pub fn process_command(command: &str, player: &mut Player, world: &mut World) -> String {
-    //figured it would work best like argv
-    let parts: Vec<&str> = command.trim().split_whitespace().collect();
+// Takes one line of input and returns the response. What the input means depends on the
+// session's mode: a regular command, a combat action, or the answer to a question.
+pub fn process_command(command: &str, session: &mut Session, commands: &CommandRegistry) -> String {
+    let response = dispatch_command(command, session, commands);
+    after_command(response, session)
+}
 
-    if parts.is_empty() {
-        return "I don't understand that command.".to_string();
+// Runs after every command no matter what mode we're in: anything that reacts to what just
+// happened (event triggers, then quest progress) gets a chance to add to the response. Last of
+// all, if the player ended up dead, the world's death rules kick in, see death.rs
+fn after_command(mut response: String, session: &mut Session) -> String {
+    if session.is_over() {
+        return response;
     }
-    //match on main command
-    match parts[0] {
-        "go" | "move" => {
-            //moves rooms
-            if parts.len() < 2 {
-                //moving needs a direction
-                "Go where?".to_string()
-            } else {
-                match player.move_room(parts[1], world) {
-                    //if we get an Ok for a move, we make it happen
-                    Ok(_) => {
-                        let current_room = &world.rooms[player.current_room];
-                        format!(
-                            "You moved to: {}.\n{}",
-                            current_room.name, current_room.description
-                        )
+    session.turn += 1;
+    let events = std::mem::take(&mut session.events);
+
+    let mut messages = triggers::run_triggers(&session.player, &mut session.world, session.turn);
+    messages.extend(quests::update_quests(&mut session.player, &mut session.world, &events));
+    for message in messages {
+        response.push_str("\n\n");
+        response.push_str(&message);
+    }
+
+    if session.player.health_points <= 0 {
+        response.push_str("\n\n");
+        response.push_str(&death::die(session));
+    }
+    response
+}
+
+fn dispatch_command(command: &str, session: &mut Session, commands: &CommandRegistry) -> String {
+    // what the command is and what it's about, see parser.rs
+    let input = Input::parse(command, commands);
+    let found = commands.find(&input.verb);
+
+    // dead for good, there's nothing left to do
+    if session.is_over() {
+        return "The game is over.".to_string();
+    }
+
+    // some commands (saving and loading, help) work no matter what the player is in the middle of
+    if let Some(command) = found.filter(|c| c.anytime) {
+        return command.run(&input, session, commands);
+    }
+
+    match &mut session.mode {
+        Mode::Explore => match found {
+            Some(command) => command.run(&input, session, commands),
+            None if input.verb.is_empty() => "I don't understand that command.".to_string(),
+            None => {
+                // a bare exit name ("stairs") is a move, same as a bare direction
+                let exit = session.world.visible_exit(&session.player.current_room, &input.verb);
+                if let Some(go) = commands.find("go").filter(|_| exit.is_some() && input.rest.is_empty()) {
+                    let input = Input {
+                        verb: go.name.clone(),
+                        rest: input.verb.clone(),
+                        object: input.verb.clone(),
+                        ..Input::default()
+                    };
+                    return go.run(&input, session, commands);
+                }
+                //generic response to things we dont' recognize :) unless it's a typo of something we do.
+                // a bare direction is a move, so ways out count too. exits that aren't directions need a "go"
+                let verbs = commands.iter().flat_map(|c| std::iter::once(&c.name).chain(&c.aliases)).cloned();
+                let exits = session.world.visible_exits(&session.player.current_room).into_iter().map(|exit| {
+                    if parser::direction(exit).is_some() {
+                        exit.clone()
+                    } else {
+                        format!("go {}", exit)
                     }
-                    Err(err) => err,
+                });
+                let directions = parser::DIRECTIONS.iter().map(|d| d.to_string());
+                let candidates: Vec<String> = verbs.chain(exits).chain(directions).collect();
+                match parser::suggest(&input.verb, candidates.iter().map(|c| c.as_str())) {
+                    Some(verb) => format!("Unknown command. Did you mean '{}'?", verb),
+                    None => "Unknown command.".to_string(),
                 }
             }
+        },
+        Mode::ConfirmAttack(npc) => {
+            let npc = npc.clone();
+            let answer = command.trim().to_lowercase();
+            if answer == "y" || answer == "yes" {
+                start_combat(session, &npc)
+            } else {
+                session.mode = Mode::Explore;
+                "You decide not to attack.".to_string()
+            }
         }
-        "look" => {
-            //comment update test commit fix
-            //shows room description and debug print room items and exits
-            let current_room = &world.rooms[player.current_room];
-            format!(
-                "{}\n\
-                Items here: {:?}\n\
-                Exits: {:?}\n\
-                NPCs: {:?}",
-                current_room.description,
-                current_room
-                    .items
-                    .iter()
-                    .map(|i| &i.name) //we only need the names, not the entire item struct
-                    .collect::<Vec<_>>(),
-                current_room.exits.keys().collect::<Vec<_>>(),
-                world
-                    .npcs
-                    .iter()
-                    .filter(|i| i.current_room == player.current_room)
-                    .map(|i| &i.name)
-                    .collect::<Vec<_>>()
-            )
+        Mode::Combat(combat) => {
+            let response = combat.handle_input(
+                command,
+                &mut session.player,
+                &mut session.world,
+                &mut session.rng,
+            );
+            if combat.is_over() {
+                session.mode = Mode::Explore;
+            }
+            response
         }
-        "inventory" => {
-            //grabs player inventory and shows it. we only need the item names displayed
-            let items = player.inventory.iter().map(|i| &i.name).collect::<Vec<_>>();
-            if items.is_empty() {
-                "You are not carrying anything.".to_string()
-            } else {
-                format!("You are carrying: {:?}", items)
+        Mode::Dialogue(conversation) => {
+            let response =
+                conversation.handle_input(command, &mut session.player, &mut session.world);
+            if conversation.is_over() {
+                session.mode = Mode::Explore;
             }
+            response
         }
-        "take" => {
-            //takes item from the room, clones it to player inventory and removes item from room
-            if parts.len() < 2 {
-                //needs an item to take
-                "Take what?".to_string()
-            } else {
-                let item_name = parts[1];
+        Mode::Choosing { input, phrase, options, .. } => {
+            let (mut input, phrase, options) = (input.clone(), phrase.clone(), options.clone());
+            session.mode = Mode::Explore;
 
-                // first, does this item exist in the room?
-                if let Some(room_item) = world.rooms[player.current_room]
-                    .items
-                    .iter_mut()
-                    .find(|i| i.name == item_name)
-                {
-                    if room_item.can_take {
-                        // next, can the item be taken?
-                        let item = room_item.clone(); // clone it
-                        player.take_item(item); // to own it :)
-                                                // Remove the item from the room's items
-                        world.rooms[player.current_room]
-                            .items
-                            .retain(|i| i.name != item_name); //need to modify room items in place, rather than filter
-                        format!("You picked up the {}.", item_name)
+            // a number or enough of a name to tell them apart picks one
+            let answer = parser::strip_articles(command);
+            let chosen = match answer.parse::<usize>() {
+                Ok(n) => n.checked_sub(1).and_then(|i| options.get(i)),
+                Err(_) => match parser::lookup(&options, &answer, |o| (o, "")) {
+                    Lookup::Found(option) => Some(option),
+                    _ => None,
+                },
+            };
+            match (chosen, commands.find(&input.verb)) {
+                (Some(name), Some(command)) => {
+                    if input.object == phrase {
+                        input.object = name.clone();
                     } else {
-                        "That item cannot be taken.".to_string()
+                        input.target = name.clone();
                     }
-                } else {
-                    "There's no such item here.".to_string()
+                    command.run(&input, session, commands)
                 }
+                // anything else is a new command, the question is forgotten
+                _ => dispatch_command(command, session, commands),
             }
         }
-        "drop" => {
-            if parts.len() < 2 {
-                "Drop what?".to_string()
-            } else {
-                let item_name = parts[1];
+        Mode::GameOver => unreachable!("handled above"),
+    }
+}
 
-                if let Some(drop_item) = player.inventory.iter_mut().find(|i| i.name == item_name) {
-                    let item = drop_item.clone();
-                    world.rooms[player.current_room].items.push(item);
-                    player.remove_item(item_name);
-                    format!("You dropped the {}.", item_name)
-                } else {
-                    "You don't have that.".to_string()
-                }
+// The commands every game starts with. A game can register its own on top, or replace and remove
+// these by name, see registry.rs
+pub fn builtins() -> CommandRegistry {
+    let mut commands = CommandRegistry::new();
+    commands.register(Command::new("look", "look", "Describe the room you're in.", look_command).alias("l"));
+    commands.register(
+        Command::new("go", "go <direction>", "Walk through an exit.", go_command)
+            .alias("move")
+            .alias("walk")
+            .details("Directions are whatever `look` lists under Exits, not just north/south/east/west."),
+    );
+    commands.register(
+        Command::new("travel", "travel <room>", "Walk back to a room you've already been to.", travel_command)
+            .details("Takes the shortest way through rooms you've visited, a turn per room, and stops if something hostile is in the way."),
+    );
+    commands.register(
+        Command::new("examine", "examine <thing>", "Take a closer look at something or someone.", examine_command)
+            .alias("x")
+            .alias("inspect")
+            .alias("look at")
+            .alias("look in")
+            .details("Works on items in the room, what you're carrying or wearing, npcs and anything else the room describes. `look in <container>` shows what's inside."),
+    );
+    commands.register(Command::new("map", "map", "Draw a map of the rooms you've explored.", map_command));
+    commands.register(Command::new(
+        "unlock",
+        "unlock <direction> with <item>",
+        "Unlock a door or a container with a key.",
+        unlock_command,
+    ).details("If there's only one door in the room, `unlock door with <item>` works too. Chests and other containers unlock the same way, `unlock chest with <item>`."));
+    commands.register(Command::new("open", "open <direction>", "Open a door or a container.", open_or_close_command));
+    commands.register(Command::new("close", "close <direction>", "Close a door or a container.", open_or_close_command));
+    commands.register(
+        Command::new("take", "take <item>", "Pick up an item in the room.", take_command)
+            .alias("get")
+            .alias("grab")
+            .alias("pick up")
+            .details("`take <item> from <container>` takes it out of an open chest, bag or anything else that holds things."),
+    );
+    commands.register(Command::new(
+        "put",
+        "put <item> in <container>",
+        "Put something you're carrying into a chest, bag or other container.",
+        put_command,
+    ).details("The container has to be open. Containers can go in other containers."));
+    commands.register(
+        Command::new("drop", "drop <item>", "Put down an item you're carrying.", drop_command).alias("put down"),
+    );
+    commands.register(
+        Command::new("inventory", "inventory", "List what you're carrying.", inventory_command)
+            .alias("i")
+            .alias("inv"),
+    );
+    commands.register(Command::new("eat", "eat <item>", "Eat something to get some HP back.", eat_command));
+    commands.register(
+        Command::new("equip", "equip <item>", "Wear or wield an item.", equip_command)
+            .alias("wear")
+            .alias("wield")
+            .details("Each slot holds one item, equipping into a full slot puts the old one back in your inventory."),
+    );
+    commands.register(
+        Command::new("unequip", "unequip <item>", "Take off an equipped item.", unequip_command)
+            .alias("remove")
+            .alias("take off"),
+    );
+    commands.register(Command::new(
+        "give",
+        "give <item> to <npc>",
+        "Hand an item to someone, they might give you something back.",
+        give_command,
+    ).details("Equipped items have to be unequipped first."));
+    commands.register(
+        Command::new("talk", "talk to <npc>", "Talk to someone in the room.", talk_command).alias("speak"),
+    );
+    commands.register(
+        Command::new("fight", "fight <npc>", "Start a fight with someone in the room.", fight_command)
+            .alias("attack")
+            .alias("hit")
+            .alias("kill")
+            .details("Every other hostile npc in the room joins in. Attacking someone friendly asks first."),
+    );
+    commands.register(Command::new("status", "status", "Show your HP, stats, level and equipment.", status_command));
+    commands.register(Command::new("skills", "skills", "List the skills you know.", skills_command));
+    commands.register(
+        Command::new("quests", "quests", "List your quests and how far along they are.", quests_command).alias("journal"),
+    );
+    commands.register(
+        Command::new("save", "save <slot>", "Save the game to a named slot.", save_command)
+            .details("Works in the middle of a fight or a conversation too, loading puts you right back there.")
+            .anytime(),
+    );
+    commands.register(Command::new("load", "load <slot>", "Load a saved game from a slot.", save_command).anytime());
+    commands.register(
+        Command::with_registry("help", "help [command]", "Show this, or more about one command.", help_command).anytime(),
+    );
+    commands
+}
+
+// fights whoever was picked, and every other hostile npc in the room joins in
+fn start_combat(session: &mut Session, npc: &NpcId) -> String {
+    let mut enemies = vec![npc.clone()];
+    enemies.extend(
+        session
+            .world
+            .npcs_in(&session.player.current_room)
+            .filter(|other| other.hostile && &other.id != npc)
+            .map(|other| other.id.clone()),
+    );
+
+    let (combat, intro) =
+        Combat::start_combat(&mut session.player, &mut session.world, enemies, &mut session.rng);
+    // enemies that act first can end it before the player gets a turn
+    session.mode = if combat.is_over() {
+        Mode::Explore
+    } else {
+        Mode::Combat(combat)
+    };
+    intro
+}
+
+// npcs with a dialogue tree for this room start a conversation, picked by number.
+// everyone else just says their next line
+fn talk_to(session: &mut Session, npc: &NpcId) -> String {
+    match Conversation::start(npc, &session.player, &session.world) {
+        Some((conversation, text)) => {
+            if !conversation.is_over() {
+                session.mode = Mode::Dialogue(conversation);
             }
+            text
         }
-        "talk" => {
-            if parts.len() < 2 {
-                "Talk to whom?".to_string()
-            } else {
-                let npc_name = parts[1];
-
-                if parts[1] == "self" {
-                    "Why are you talking to yourself?".to_string()
-                } else if let Some(npc) = world
-                    .npcs
-                    .iter_mut() // we need mutable references here
-                    .find(|i| i.name == npc_name)
-                {
-                    if npc.current_room == player.current_room {
-                        npc.speak_dialogue()
-                    } else {
-                        "They aren't here.".to_string()
-                    }
-                } else {
-                    "They aren't here".to_string()
-                }
+        None => session
+            .world
+            .npc_mut(npc)
+            .map(|npc| npc.speak_dialogue())
+            .unwrap_or_default(),
+    }
+}
+
+// Walks to a room the player has been to before, one room at a time. Every room on the way is its
+// own turn so triggers and quests see it, and the walk stops early if something hostile is there
+fn travel_to(session: &mut Session, destination: &str) -> String {
+    let player = &session.player;
+    let world = &session.world;
+    let Some(target) = world.find_visited_room(destination, &player.visited) else {
+        return "You haven't been anywhere called that.".to_string();
+    };
+    if target == player.current_room {
+        return "You're already there.".to_string();
+    }
+    let Some(path) = world.find_path(&player.current_room, &target, &player.visited) else {
+        return "You don't know a way there from here.".to_string();
+    };
+
+    let mut steps = Vec::new();
+    for (i, direction) in path.iter().enumerate() {
+        if let Err(err) = session.player.move_room(direction, &session.world) {
+            steps.push(err.to_string());
+            break;
+        }
+        let room = &session.world.rooms[&session.player.current_room];
+        let hostile = session
+            .world
+            .npcs_in(&session.player.current_room)
+            .find(|npc| npc.hostile)
+            .map(|npc| npc.name.clone());
+
+        if i + 1 == path.len() {
+            // the last step gets after_command from process_command like any other command
+            steps.push(format!("You arrive at: {}.\n{}", room.name, room.description));
+        } else if let Some(name) = hostile {
+            steps.push(format!(
+                "You go {} to {}. The {} blocks your way!",
+                direction, room.name, name
+            ));
+            break;
+        } else {
+            let entered = session.player.current_room.clone();
+            let step = format!("You go {} to {}.", direction, room.name);
+            steps.push(after_command(step, session));
+            // a trigger or dying on the way (a restart, a respawn, game over) means the rest of the route is gone
+            if session.player.current_room != entered || !matches!(session.mode, Mode::Explore) {
+                break;
             }
         }
-        "help" => {
-            // end each line as \n\ for readability for both dev and user
-            "this is the help page\n\
-            you can view it like this and\n\
-            it stays in place\n\
-            test newline\
-            "
-            .to_string()
+    }
+    steps.join("\n")
+}
+
+// Works out which of some things a phrase from the input means. Err is what to tell the player
+// instead: a question when it fits more than one (the mode switches so the answer comes in with
+// the next command), or not_found plus a "did you mean" when it looks like a typo
+fn pick<'a, T>(
+    mode: &mut Mode,
+    input: &Input,
+    phrase: &str,
+    things: Vec<&'a T>,
+    name_and_description: impl Fn(&T) -> (&str, &str),
+    the: &str,
+    not_found: &str,
+) -> Result<&'a T, String> {
+    match parser::lookup(things.iter().copied(), phrase, &name_and_description) {
+        Lookup::Found(thing) => Ok(thing),
+        Lookup::Ambiguous(found) => {
+            let options: Vec<String> = found.iter().map(|t| name_and_description(t).0.to_string()).collect();
+            let listed: Vec<String> = options.iter().map(|name| format!("{}{}", the, name)).collect();
+            let (last, rest) = listed.split_last().expect("ambiguous means at least two");
+            let noun = phrase.split_whitespace().last().unwrap_or(phrase);
+            let question = format!("Which {} do you mean: {} or {}?", noun, rest.join(", "), last);
+            *mode = Mode::Choosing {
+                input: input.clone(),
+                phrase: phrase.to_string(),
+                options,
+                question: question.clone(),
+            };
+            Err(question)
         }
-        "eat" => {
-            if parts.len() < 2 {
-                "Eat what?".to_string()
-            } else {
-                let item_name = parts[1];
+        Lookup::NotFound => {
+            let names = things.iter().map(|t| name_and_description(t).0);
+            match parser::suggest(phrase, names) {
+                Some(name) => Err(format!("{} Did you mean {}{}?", not_found, the, name)),
+                None => Err(not_found.to_string()),
+            }
+        }
+    }
+}
 
-                if let Some(drop_item) = player.inventory.iter_mut().find(|i| i.name == item_name) {
-                    if drop_item.can_eat == true {
-                        player.health_points += drop_item.heal_amount;
-                        player.remove_item(parts[1]);
-                        format!("You ate the {}.", item_name)
-                    } else {
-                        "You can't eat that.".to_string()
-                    }
+// The real name of whichever of these items the player means, see pick
+fn pick_item<'a>(
+    mode: &mut Mode,
+    input: &Input,
+    phrase: &str,
+    items: impl IntoIterator<Item = &'a Item>,
+    not_found: &str,
+) -> Result<String, String> {
+    let items: Vec<&Item> = items.into_iter().collect();
+    pick(mode, input, phrase, items, |i| (&i.name, &i.description), "the ", not_found).map(|i| i.name.clone())
+}
+
+// the npc in this room the player means, see pick
+fn pick_npc(
+    mode: &mut Mode,
+    input: &Input,
+    phrase: &str,
+    world: &World,
+    room: &RoomId,
+    not_found: &str,
+) -> Result<NpcId, String> {
+    let here: Vec<&Npc> = world.npcs_in(room).collect();
+    pick(mode, input, phrase, here, |n| (&n.name, ""), "", not_found).map(|n| n.id.clone())
+}
+
+// Which exit the player means: a direction ("n" works for north), an exit's name, or a phrase
+// with an exit's name in it ("down the stairs")
+fn exit_direction(world: &World, room: &RoomId, phrase: &str) -> Option<String> {
+    let direction = parser::direction(phrase).unwrap_or(phrase);
+    if world.visible_exit(room, direction).is_some() {
+        return Some(direction.to_string());
+    }
+    phrase
+        .split_whitespace()
+        .find(|word| world.visible_exit(room, word).is_some())
+        .map(|word| word.to_string())
+}
+
+// what to say when there's no exit by that name, with a suggestion if it looks like a typo
+fn no_exit(world: &World, room: &RoomId, phrase: &str, message: &str) -> String {
+    let exits = world.visible_exits(room);
+    match parser::suggest(phrase, exits.into_iter().map(|e| e.as_str())) {
+        Some(exit) => format!("{} Did you mean {}?", message, exit),
+        None => message.to_string(),
+    }
+}
+
+// Which exit a door command means. Any exit works, and so does "door" (or nothing at all) when
+// there's just the one door in the room
+fn door_direction(world: &World, room: &RoomId, phrase: &str) -> Option<String> {
+    if let Some(direction) = exit_direction(world, room, phrase) {
+        return Some(direction);
+    }
+    if !phrase.is_empty() && phrase != "door" {
+        return None;
+    }
+    let doors: Vec<&String> = world
+        .visible_exits(room)
+        .into_iter()
+        .filter(|dir| world.visible_exit(room, dir).is_some_and(|e| e.door.is_some()))
+        .collect();
+    match doors[..] {
+        [only] => Some(only.clone()),
+        _ => None,
+    }
+}
+
+// Every container the player can get at: carried or in the room, and any inside those that are open
+fn reachable_containers<'a>(player: &'a Player, world: &'a World) -> Vec<&'a Item> {
+    let room = &world.rooms[&player.current_room];
+    containers::reachable(&player.inventory)
+        .into_iter()
+        .chain(containers::reachable(&room.items))
+        .filter(|i| i.container.is_some())
+        .collect()
+}
+
+// does a phrase mean a container rather than a door, for the commands that work on both
+fn means_container(player: &Player, world: &World, phrase: &str) -> bool {
+    let found = parser::lookup(reachable_containers(player, world), phrase, |i| (&i.name, &i.description));
+    !phrase.is_empty() && !matches!(found, Lookup::NotFound)
+}
+
+// a container the player can get at by its real name, the carried one first
+fn container_mut<'a>(player: &'a mut Player, world: &'a mut World, name: &str) -> Option<&'a mut Container> {
+    let item = match containers::find_mut(&mut player.inventory, name) {
+        Some(item) => item,
+        None => containers::find_mut(&mut world.room_mut(&player.current_room).items, name)?,
+    };
+    item.container.as_mut()
+}
+
+// hands an item to a friendly npc if they have a trade for it, and takes whatever they give back
+fn give_item(player: &mut Player, npc: &mut Npc, item_name: &str) -> String {
+    if npc.hostile {
+        return format!("The {} isn't interested in trading with you.", npc.name);
+    }
+    let Some(trade) = npc.trades.iter().find(|t| t.wants == item_name).cloned() else {
+        return format!("{} doesn't want the {}.", npc.name, item_name);
+    };
+
+    // they can only hand over what they still have
+    let reward = match &trade.gives {
+        Some(name) => match npc.inventory.iter().position(|i| &i.name == name) {
+            Some(index) => Some(npc.inventory.remove(index)),
+            None => return format!("{} has nothing left to trade for that.", npc.name),
+        },
+        None => None,
+    };
+
+    if let Some(item) = player.remove_item(item_name) {
+        npc.inventory.push(item);
+    }
+    let mut lines = vec![format!("You give the {} to {}.", item_name, npc.name)];
+    if let Some(reply) = &trade.reply {
+        lines.push(format!("{}: {}", npc.name, reply));
+    }
+    if let Some(item) = reward {
+        lines.push(format!("{} gives you the {}.", npc.name, item.name));
+        player.take_item(item);
+    }
+    lines.join("\n")
+}
+
+fn save_command(input: &Input, session: &mut Session) -> String {
+    let slot = input.rest.as_str();
+    if slot.is_empty() {
+        return if input.verb == "save" {
+            "Save to which slot?".to_string()
+        } else {
+            "Load which slot?".to_string()
+        };
+    }
+
+    if input.verb == "save" {
+        //save the whole game to a named slot in the saves folder
+        match save::save_slot(slot, session) {
+            Ok(_) => {
+                session.last_save = save::to_string(session).ok(); // where reloading on death goes back to
+                format!("Game saved to slot '{}'.", slot)
+            }
+            Err(err) => err.to_string(),
+        }
+    } else {
+        //replace the current game with a saved one
+        match save::load_slot(slot) {
+            Ok(saved) => {
+                // the world as it started doesn't change, a restart still goes back to it
+                let fresh_world = session.fresh_world.take();
+                *session = saved;
+                session.fresh_world = fresh_world;
+                session.last_save = save::to_string(session).ok();
+                let resume = match &session.mode {
+                    Mode::Combat(combat) => combat.prompt(&session.player, &session.world),
+                    Mode::Dialogue(conversation) => conversation.prompt(&session.player, &session.world),
+                    Mode::Choosing { question, .. } => question.clone(),
+                    _ => session.world.rooms[&session.player.current_room].description.clone(),
+                };
+                format!("Game loaded from slot '{}'.\n{}", slot, resume)
+            }
+            Err(err) => err.to_string(),
+        }
+    }
+}
+
+fn go_command(input: &Input, session: &mut Session) -> String {
+    let player = &mut session.player;
+    let world = &mut session.world;
+    //moves rooms
+    if input.object.is_empty() {
+        //moving needs a direction
+        "Go where?".to_string()
+    } else {
+        let direction = exit_direction(world, &player.current_room, &input.object).unwrap_or_default();
+        match player.move_room(&direction, world) {
+            //if we get an Ok for a move, we make it happen
+            Ok(_) => {
+                let current_room = &world.rooms[&player.current_room];
+                format!(
+                    "You moved to: {}.\n{}",
+                    current_room.name, current_room.description
+                )
+            }
+            Err(MoveError::NoExit) => no_exit(world, &player.current_room, &input.object, &MoveError::NoExit.to_string()),
+            Err(err) => err.to_string(),
+        }
+    }
+}
+
+fn look_command(input: &Input, session: &mut Session) -> String {
+    // "look key" is a closer look at the key
+    if !input.object.is_empty() {
+        return examine_command(input, session);
+    }
+    let player = &mut session.player;
+    let world = &mut session.world;
+    //comment update test commit fix
+    //shows room description and debug print room items and exits
+    let current_room = &world.rooms[&player.current_room];
+    format!(
+        "{}\n\
+        Items here: {:?}\n\
+        Exits: {:?}\n\
+        NPCs: {:?}",
+        current_room.description,
+        current_room
+            .items
+            .iter()
+            .map(|i| &i.name) //we only need the names, not the entire item struct
+            .collect::<Vec<_>>(),
+        world.visible_exits(&player.current_room),
+        world
+            .npcs_in(&player.current_room)
+            .map(|i| &i.name)
+            .collect::<Vec<_>>()
+    )
+}
+
+// a closer look at anything in the room or on the player, see examine.rs
+fn examine_command(input: &Input, session: &mut Session) -> String {
+    if input.object.is_empty() {
+        return "Examine what?".to_string();
+    }
+    let things = examine::visible_things(&session.player, &session.world);
+    let not_here = "You don't see that here.";
+    match pick(&mut session.mode, input, &input.object, things.iter().collect(), Thing::name_and_description, "", not_here) {
+        Ok(thing) => thing.describe(&session.player),
+        Err(response) => response,
+    }
+}
+
+fn unlock_command(input: &Input, session: &mut Session) -> String {
+    if means_container(&session.player, &session.world, &input.object) {
+        return unlock_container(input, session);
+    }
+    let player = &mut session.player;
+    let world = &mut session.world;
+    //unlock <direction> with <item>, or unlock door with <item> when there's only the one door
+    let Some(direction) = door_direction(world, &player.current_room, &input.object) else {
+        return if input.object.is_empty() {
+            "Unlock what?".to_string()
+        } else {
+            no_exit(world, &player.current_room, &input.object, "There's no exit that way.")
+        };
+    };
+    if input.target.is_empty() {
+        format!("Unlock it with what? Try: unlock {} with <item>", direction)
+    } else {
+        match world.visible_exit(&player.current_room, &direction).map(|e| &e.door) {
+            None => "There's no exit that way.".to_string(),
+            Some(None) => "There's no door that way.".to_string(),
+            Some(Some(door)) if !door.locked => "It isn't locked.".to_string(),
+            Some(Some(door)) => {
+                // only asked about once there's a lock to try it in, so a "which key?" is never thrown away
+                let carried = player.inventory.iter().chain(player.equipment.values());
+                let item_name = match pick_item(&mut session.mode, input, &input.target, carried, "You don't have that.") {
+                    Ok(name) => name,
+                    Err(response) => return response,
+                };
+                if door.key.as_ref() != Some(&item_name) {
+                    format!("The {} doesn't fit the lock.", item_name)
                 } else {
-                    "You don't have that.".to_string()
+                    world.update_door(&player.current_room, &direction, |d| d.locked = false);
+                    format!("You unlock the door with the {}.", item_name)
                 }
             }
         }
-        //status command display hp, ap, and equipment
-        "status" => {
-            let gear = player.equipment.iter().map(|i| &i.name).collect::<Vec<_>>();
-            format!(
-                "Equipment: {:?}\n\
-                HP: {}      AP: {}
-            ", gear, player.health_points, player.attack_power)
+    }
+}
+
+// unlock <container> with <item>
+fn unlock_container(input: &Input, session: &mut Session) -> String {
+    let found = reachable_containers(&session.player, &session.world);
+    let name = match pick_item(&mut session.mode, input, &input.object, found, "You don't see that here.") {
+        Ok(name) => name,
+        Err(response) => return response,
+    };
+    if input.target.is_empty() {
+        return format!("Unlock it with what? Try: unlock {} with <item>", input.object);
+    }
+    let player = &mut session.player;
+    let carried = player.inventory.iter().chain(player.equipment.values());
+    let key = match pick_item(&mut session.mode, input, &input.target, carried, "You don't have that.") {
+        Ok(name) => name,
+        Err(response) => return response,
+    };
+
+    match container_mut(player, &mut session.world, &name) {
+        None => "You don't see that here.".to_string(),
+        Some(container) if !container.locked => "It isn't locked.".to_string(),
+        Some(container) if container.key.as_ref() != Some(&key) => format!("The {} doesn't fit the lock.", key),
+        Some(container) => {
+            container.locked = false;
+            format!("You unlock the {} with the {}.", name, key)
+        }
+    }
+}
+
+fn open_or_close_command(input: &Input, session: &mut Session) -> String {
+    if means_container(&session.player, &session.world, &input.object) {
+        return open_or_close_container(input, session);
+    }
+    let player = &mut session.player;
+    let world = &mut session.world;
+    let closing = input.verb == "close";
+    let Some(direction) = door_direction(world, &player.current_room, &input.object) else {
+        return if input.object.is_empty() {
+            format!("{} what?", if closing { "Close" } else { "Open" })
+        } else {
+            no_exit(world, &player.current_room, &input.object, "There's no exit that way.")
+        };
+    };
+    match world.visible_exit(&player.current_room, &direction).map(|e| &e.door) {
+        None => "There's no exit that way.".to_string(),
+        Some(None) => "There's no door that way.".to_string(),
+        Some(Some(door)) if door.locked => "The door is locked.".to_string(),
+        Some(Some(door)) if door.closed == closing => {
+            format!("It's already {}.", if closing { "closed" } else { "open" })
+        }
+        Some(Some(_)) => {
+            world.update_door(&player.current_room, &direction, |d| d.closed = closing);
+            format!("You {} the door.", if closing { "close" } else { "open" })
+        }
+    }
+}
+
+// open <container>, close <container>. opening one shows what's inside
+fn open_or_close_container(input: &Input, session: &mut Session) -> String {
+    let closing = input.verb == "close";
+    let found = reachable_containers(&session.player, &session.world);
+    let name = match pick_item(&mut session.mode, input, &input.object, found, "You don't see that here.") {
+        Ok(name) => name,
+        Err(response) => return response,
+    };
+    match container_mut(&mut session.player, &mut session.world, &name) {
+        None => "You don't see that here.".to_string(),
+        Some(container) if container.locked => format!("The {} is locked.", name),
+        Some(container) if container.closed == closing => {
+            format!("It's already {}.", if closing { "closed" } else { "open" })
+        }
+        Some(container) => {
+            container.closed = closing;
+            if closing {
+                format!("You close the {}.", name)
+            } else {
+                format!("You open the {}.\n{}", name, container.describe())
+            }
+        }
+    }
+}
+
+fn inventory_command(_input: &Input, session: &mut Session) -> String {
+    let player = &mut session.player;
+    //grabs player inventory and shows it. we only need the item names displayed, and what's in open bags
+    let items = player.inventory.iter().map(|i| i.label()).collect::<Vec<_>>();
+    if items.is_empty() {
+        "You are not carrying anything.".to_string()
+    } else {
+        format!("You are carrying: {:?}", items)
+    }
+}
+
+fn take_command(input: &Input, session: &mut Session) -> String {
+    let player = &mut session.player;
+    let world = &mut session.world;
+    //takes item from the room, clones it to player inventory and removes item from room
+    if input.object.is_empty() {
+        //needs an item to take
+        "Take what?".to_string()
+    } else if input.preposition.as_deref() == Some("from") && !input.target.is_empty() {
+        take_from_container(input, session)
+    } else {
+        let here = &world.rooms[&player.current_room].items;
+        // scenery can be looked at but it stays where it is
+        let scenery = &world.rooms[&player.current_room].scenery;
+        if matches!(parser::lookup(here, &input.object, |i| (&i.name, &i.description)), Lookup::NotFound) {
+            if let Lookup::Found(s) = parser::lookup(scenery, &input.object, |s| (&s.name, &s.description)) {
+                return format!("You can't take the {}.", s.name);
+            }
         }
-        //equip an item
-        "equip" => {
-            if parts.len() < 2 {
-                "Equip what?".to_string()
+        let item_name = match pick_item(&mut session.mode, input, &input.object, here, "There's no such item here.") {
+            Ok(name) => name,
+            Err(response) => return response,
+        };
+
+        // first, does this item exist in the room?
+        if let Some(room_item) = world
+            .room_mut(&player.current_room)
+            .items
+            .iter_mut()
+            .find(|i| i.name == item_name)
+        {
+            if room_item.can_take {
+                // next, can the item be taken?
+                let item = room_item.clone(); // clone it
+                player.take_item(item); // to own it :)
+                                        // Remove the item from the room's items
+                world
+                    .room_mut(&player.current_room)
+                    .items
+                    .retain(|i| i.name != item_name); //need to modify room items in place, rather than filter
+                format!("You picked up the {}.", item_name)
             } else {
-                
-                let item_name = parts[1];
-                //search equipment for item name
-                if let Some(equip_item) = player.equipment.iter().find(|i| i.name == item_name) {
-                    "You've already equipped that.".to_string()
-                } else {
-                    //find item in inventory
-                    if let Some(equip_item) = player.inventory.iter().find(|i| i.name == item_name) {
-                        let item = equip_item.clone();
-                        player.attack_power += item.attack_increase_amount;
-                        player.equip_item(item); 
-                        format!("You equipped the {}.", item_name)
+                "That item cannot be taken.".to_string()
+            }
+        } else {
+            "There's no such item here.".to_string()
+        }
+    }
+}
 
-                    } else {
-                        "You don't have that.".to_string()
-                    }
-                }
-                
+// take <item> from <container>, the container can be in the room or carried
+fn take_from_container(input: &Input, session: &mut Session) -> String {
+    let found = reachable_containers(&session.player, &session.world);
+    let name = match pick_item(&mut session.mode, input, &input.target, found, "You don't see that here.") {
+        Ok(name) => name,
+        Err(response) => return response,
+    };
+    let Some(container) = container_mut(&mut session.player, &mut session.world, &name) else {
+        return "You don't see that here.".to_string();
+    };
+    if container.closed {
+        return format!("The {} is closed.", name);
+    }
+    let not_inside = format!("There's no such item in the {}.", name);
+    let item_name = match pick_item(&mut session.mode, input, &input.object, &container.items, &not_inside) {
+        Ok(name) => name,
+        Err(response) => return response,
+    };
+
+    let index = container.items.iter().position(|i| i.name == item_name).expect("item was just picked");
+    if !container.items[index].can_take {
+        return "That item cannot be taken.".to_string();
+    }
+    let item = container.items.remove(index);
+    session.player.take_item(item);
+    format!("You take the {} from the {}.", item_name, name)
+}
+
+// put <item> in <container>
+fn put_command(input: &Input, session: &mut Session) -> String {
+    if input.object.is_empty() {
+        return "Put what?".to_string();
+    }
+    if input.target.is_empty() {
+        return format!("Put it in what? Try: put {} in <container>", input.object);
+    }
+    let player = &mut session.player;
+    let world = &mut session.world;
+    let item_name = match pick_item(&mut session.mode, input, &input.object, &player.inventory, "You don't have that.") {
+        Ok(name) => name,
+        Err(response) => return response,
+    };
+    let found = reachable_containers(player, world);
+    let name = match pick_item(&mut session.mode, input, &input.target, found, "You don't see that here.") {
+        Ok(name) => name,
+        Err(response) => return response,
+    };
+
+    // out of the inventory first. if the container was inside the item it can't be found anymore,
+    // and a bag can't go inside itself
+    let index = player.inventory.iter().position(|i| i.name == item_name).expect("item was just picked");
+    let item = player.inventory.remove(index);
+    let response = match container_mut(player, world, &name) {
+        Some(container) if container.closed => format!("The {} is closed.", name),
+        Some(container) => {
+            container.items.push(item);
+            return format!("You put the {} in the {}.", item_name, name);
+        }
+        None => format!("You can't put the {} inside itself.", item_name),
+    };
+    player.inventory.insert(index, item);
+    response
+}
+
+fn drop_command(input: &Input, session: &mut Session) -> String {
+    let player = &mut session.player;
+    let world = &mut session.world;
+    if input.object.is_empty() {
+        "Drop what?".to_string()
+    } else {
+        let item_name = match pick_item(&mut session.mode, input, &input.object, &player.inventory, "You don't have that.") {
+            Ok(name) => name,
+            Err(response) => return response,
+        };
+
+        if let Some(drop_item) = player.inventory.iter_mut().find(|i| i.name == item_name) {
+            let item = drop_item.clone();
+            world.room_mut(&player.current_room).items.push(item);
+            player.remove_item(&item_name);
+            format!("You dropped the {}.", item_name)
+        } else {
+            "You don't have that.".to_string()
+        }
+    }
+}
+
+fn give_command(input: &Input, session: &mut Session) -> String {
+    let player = &mut session.player;
+    let world = &mut session.world;
+    //give <item> to <npc>
+    if input.object.is_empty() {
+        "Give what?".to_string()
+    } else if input.target.is_empty() {
+        format!("Give it to whom? Try: give {} to <npc>", input.object)
+    } else {
+        let carried = player.inventory.iter().chain(player.equipment.values());
+        let item_name = match pick_item(&mut session.mode, input, &input.object, carried, "You don't have that.") {
+            Ok(name) => name,
+            Err(response) => return response,
+        };
+        let not_here = "There's no one here by that name.";
+        let id = match pick_npc(&mut session.mode, input, &input.target, world, &player.current_room, not_here) {
+            Ok(id) => Some(id),
+            Err(response) => return response,
+        };
+
+        if player.equipment.values().any(|i| i.name == item_name) {
+            "You'll have to unequip it first.".to_string()
+        } else if !player.inventory.iter().any(|i| i.name == item_name) {
+            "You don't have that.".to_string()
+        } else if let Some(npc) = id.and_then(|id| world.npc_mut(&id)) {
+            give_item(player, npc, &item_name)
+        } else {
+            "There's no one here by that name.".to_string()
+        }
+    }
+}
+
+fn talk_command(input: &Input, session: &mut Session) -> String {
+    let player = &mut session.player;
+    let world = &mut session.world;
+    if input.object.is_empty() {
+        "Talk to whom?".to_string()
+    } else if ["self", "yourself", "myself", "me"].contains(&input.object.as_str()) {
+        "Why are you talking to yourself?".to_string()
+    } else {
+        match pick_npc(&mut session.mode, input, &input.object, world, &player.current_room, "They aren't here.") {
+            Ok(npc) => {
+                session.events.push(GameEvent::Talked(npc.clone()));
+                talk_to(session, &npc)
             }
+            Err(response) => response,
+        }
+    }
+}
+
+//list active and completed quests with their objectives so far
+fn quests_command(_input: &Input, session: &mut Session) -> String {
+    quests::journal(&session.world)
+}
+
+//draw the explored part of the world
+fn map_command(_input: &Input, session: &mut Session) -> String {
+    map::render(&session.world, &session.player)
+}
+
+//walk back to somewhere already visited, room names can have spaces in them
+fn travel_command(input: &Input, session: &mut Session) -> String {
+    if input.object.is_empty() {
+        "Travel where?".to_string()
+    } else {
+        travel_to(session, &input.object)
+    }
+}
+
+// Lists what can be typed right now, made from the registry so it never goes out of date.
+// `help <command>` shows everything about one command
+fn help_command(input: &Input, session: &mut Session, commands: &CommandRegistry) -> String {
+    let exploring = matches!(session.mode, Mode::Explore);
+
+    if !input.object.is_empty() {
+        let Some(command) = commands.find(&input.object) else {
+            return format!("There's no command called '{}'.", input.object);
+        };
+        let mut lines = vec![command.usage.clone(), command.help.clone()];
+        lines.extend(command.details.clone());
+        if !command.aliases.is_empty() {
+            lines.push(format!("Also: {}", command.aliases.join(", ")));
+        }
+        if !command.anytime && !exploring {
+            lines.push("(not right now, only while exploring)".to_string());
         }
-        //unequip an item
-        "unequip" => {
-            if parts.len() < 2 {
-                "Unequip what?".to_string()
+        return lines.join("\n");
+    }
+
+    // (usage, summary) for everything that works in the current mode
+    let mut entries: Vec<(String, String)> = match &session.mode {
+        Mode::Explore => Vec::new(),
+        Mode::ConfirmAttack(_) => vec![
+            ("y, yes".to_string(), "Attack them.".to_string()),
+            ("n, no".to_string(), "Leave them be.".to_string()),
+        ],
+        Mode::Combat(_) => combat::HELP.iter().map(|(u, h)| (u.to_string(), h.to_string())).collect(),
+        Mode::Dialogue(_) => dialogue::HELP.iter().map(|(u, h)| (u.to_string(), h.to_string())).collect(),
+        Mode::Choosing { .. } => vec![
+            ("<number> or <name>".to_string(), "Pick one of them.".to_string()),
+            ("anything else".to_string(), "Forget the question and do that instead.".to_string()),
+        ],
+        Mode::GameOver => Vec::new(),
+    };
+    entries.extend(
+        commands
+            .iter()
+            .filter(|c| exploring || c.anytime)
+            .map(|c| (c.usage.clone(), c.help.clone())),
+    );
+
+    let width = entries.iter().map(|(usage, _)| usage.len()).max().unwrap_or(0);
+    let mut lines = vec![match session.mode {
+        Mode::Explore => "Commands:".to_string(),
+        Mode::Combat(_) => "In a fight:".to_string(),
+        Mode::Dialogue(_) => "In a conversation:".to_string(),
+        Mode::ConfirmAttack(_) | Mode::Choosing { .. } => "Answering:".to_string(),
+        Mode::GameOver => "The game is over.".to_string(),
+    }];
+    for (usage, summary) in entries {
+        lines.push(format!("  {:width$}  {}", usage, summary, width = width));
+    }
+    lines.join("\n")
+}
+
+fn eat_command(input: &Input, session: &mut Session) -> String {
+    let player = &mut session.player;
+    if input.object.is_empty() {
+        "Eat what?".to_string()
+    } else {
+        let item_name = match pick_item(&mut session.mode, input, &input.object, &player.inventory, "You don't have that.") {
+            Ok(name) => name,
+            Err(response) => return response,
+        };
+
+        if let Some(drop_item) = player.inventory.iter_mut().find(|i| i.name == item_name) {
+            if drop_item.can_eat {
+                player.eat_item(&item_name);
+                format!("You ate the {}.", item_name)
             } else {
-                let item_name = parts[1];
-                //search equipment for item name
-                if let Some(equip_item) = player.equipment.iter().find(|i| i.name == item_name) {
-                    player.attack_power -= equip_item.attack_increase_amount;
-                    player.unequip_item(item_name);
+                "You can't eat that.".to_string()
+            }
+        } else {
+            "You don't have that.".to_string()
+        }
+    }
+}
 
-                    format!("You unequipped the {}", item_name)
-                } else {
-                    "You don't have that equipped.".to_string()
-                }
+//status command display hp, ap, and equipment
+fn status_command(_input: &Input, session: &mut Session) -> String {
+    let player = &mut session.player;
+    let gear = player
+        .equipment
+        .iter()
+        .map(|(slot, item)| format!("{}: {}", slot, item.name))
+        .collect::<Vec<_>>();
+    format!(
+        "Equipment: {:?}\n\
+        HP: {}      AP: {}      DEF: {}\n\
+        Level: {}   XP: {}/{}
+    ", gear, player.health_points, player.attack(), player.defense(), player.level, player.xp, player.xp_to_next_level())
+}
 
+//list the skills the player knows, use them in combat with [S]kill
+fn skills_command(_input: &Input, session: &mut Session) -> String {
+    let player = &mut session.player;
+    let world = &mut session.world;
+    let mut lines = vec![format!(
+        "Level {} ({}/{} XP to the next level)",
+        player.level,
+        player.xp,
+        player.xp_to_next_level()
+    )];
+    if player.skills.is_empty() {
+        lines.push("You don't know any skills yet.".to_string());
+    }
+    // same order as the combat menu
+    for skill in player.skills.iter().filter_map(|name| world.skills.iter().find(|s| &s.name == name)) {
+        lines.push(format!(
+            "  {} - {} ({}, cooldown {})",
+            skill.name,
+            skill.description,
+            skill.effect.summary(),
+            skill.cooldown
+        ));
+    }
+    if let Some(next) = world.skills.iter().filter(|s| s.level > player.level).min_by_key(|s| s.level) {
+        lines.push(format!("Next skill: {} at level {}", next.name, next.level));
+    }
+    lines.join("\n")
+}
+
+//equip an item
+fn equip_command(input: &Input, session: &mut Session) -> String {
+    let player = &mut session.player;
+    if input.object.is_empty() {
+        "Equip what?".to_string()
+    } else {
+        let carried = player.inventory.iter().chain(player.equipment.values());
+        let item_name = match pick_item(&mut session.mode, input, &input.object, carried, "You don't have that.") {
+            Ok(name) => name,
+            Err(response) => return response,
+        };
+        //search equipment for item name
+        if player.equipment.values().any(|i| i.name == item_name) {
+            "You've already equipped that.".to_string()
+        } else {
+            //moves it out of the inventory, swapping out whatever was in the slot
+            match player.equip_item(&item_name) {
+                Ok(Some(old)) => format!("You put away the {} and equip the {}.", old.name, item_name),
+                Ok(None) => format!("You equipped the {}.", item_name),
+                Err(err) => err,
             }
         }
-        "fight" | "attack" => {
-            if parts.len() < 2 {
-                "Fight whom?".to_string()
+    }
+}
+
+//unequip an item
+fn unequip_command(input: &Input, session: &mut Session) -> String {
+    let player = &mut session.player;
+    if input.object.is_empty() {
+        "Unequip what?".to_string()
+    } else {
+        let worn = player.equipment.values();
+        let item_name = match pick_item(&mut session.mode, input, &input.object, worn, "You don't have that equipped.") {
+            Ok(name) => name,
+            Err(response) => return response,
+        };
+        //back into the inventory it goes
+        if player.unequip_item(&item_name).is_some() {
+            format!("You unequipped the {}", item_name)
+        } else {
+            "You don't have that equipped.".to_string()
+        }
+    }
+}
+
+fn fight_command(input: &Input, session: &mut Session) -> String {
+    let player = &mut session.player;
+    let world = &mut session.world;
+    if input.object.is_empty() {
+        "Fight whom?".to_string()
+    } else {
+        // Find the NPC in the current room
+        let not_here = "There's no one here by that name to fight.";
+        let target = match pick_npc(&mut session.mode, input, &input.object, world, &player.current_room, not_here) {
+            Ok(id) => world.npc(&id),
+            Err(response) => return response,
+        }
+            .map(|npc| (npc.id.clone(), npc.name.clone(), npc.hostile));
+
+        if let Some((id, name, hostile)) = target {
+            if hostile {
+                // Hostile NPC - start combat immediately
+                start_combat(session, &id)
             } else {
-                let npc_name = parts[1];
-                
-                // Find the NPC in the current room
-                if let Some((npc_index, _)) = world
-                    .npcs
-                    .iter()
-                    .enumerate()
-                    .find(|(_, npc)| npc.name == npc_name && npc.current_room == player.current_room)
-                {
-                    let npc = &world.npcs[npc_index];
-                    
-                    if npc.hostile {
-                        // Hostile NPC - start combat immediately
-                        Combat::start_combat(player, world, npc_index)
-                    } else {
-                        // Non-hostile NPC - ask for confirmation
-                        use std::io::{self, Write};
-                        print!("Are you sure you want to attack {}? (y/n): ", npc.name);
-                        io::stdout().flush().unwrap();
-                        
-                        let mut input = String::new();
-                        io::stdin().read_line(&mut input).unwrap();
-                        
-                        if input.trim().to_lowercase() == "y" || input.trim().to_lowercase() == "yes" {
-                            Combat::start_combat(player, world, npc_index)
-                        } else {
-                            "You decide not to attack.".to_string()
-                        }
-                    }
-                } else {
-                    "There's no one here by that name to fight.".to_string()
-                }
+                // Non-hostile NPC - ask for confirmation, the answer comes in with the next command
+                let question = format!("Are you sure you want to attack {}? (y/n)", name);
+                session.mode = Mode::ConfirmAttack(id);
+                question
             }
+        } else {
+            "There's no one here by that name to fight.".to_string()
         }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{builtins, process_command};
+    use crate::game::testing::{new_session, run_script, WORLD};
+
+    #[test]
+    fn bare_exit_name_moves() {
+        let responses = run_script(WORLD, 1, &["stairs"]);
+        assert!(responses[0].starts_with("You moved to: Cellar."), "{}", responses[0]);
+    }
+
+    #[test]
+    fn typos_of_exits_and_directions_get_suggestions() {
+        let responses = run_script(WORLD, 1, &["stiars", "norht"]);
+        assert_eq!(responses[0], "Unknown command. Did you mean 'go stairs'?");
+        assert_eq!(responses[1], "Unknown command. Did you mean 'north'?");
+    }
+
+    #[test]
+    fn help_is_a_command_like_any_other() {
+        let responses = run_script(WORLD, 1, &["help", "help help"]);
+        assert!(responses[0].contains("help [command]"), "{}", responses[0]);
+        assert!(responses[1].starts_with("help [command]\nShow this"), "{}", responses[1]);
 
-        _ => "Unknown command.".to_string(), //generic response to things we dont' recognize :)
+        let mut commands = builtins();
+        commands.remove("help");
+        let response = process_command("help", &mut new_session(WORLD, 1), &commands);
+        assert_eq!(response, "Unknown command.");
     }
 }
@@ -0,0 +1,88 @@
+use crate::game::world::{Container, Item};
+
+/* CONTAINERS
+ * Chests, bags, pouches: items with a Container on them, so other items can go inside. A container
+ * is still just an item, so it can sit in a room, be carried if it can be taken, or be inside
+ * another container. What's in one can only be seen, taken out or added to while it's open, and
+ * a locked one opens with its key the same way a door does.
+ *
+ *   take coin from chest     put coin in pouch     look in chest
+ *
+ * Anything inside a carried container still counts as carried (Player::has_item), so a quest
+ * that wants the Coin doesn't care whether it's in a pouch.
+ */
+impl Item {
+    // the container, if it's one and it's open
+    pub fn open_container(&self) -> Option<&Container> {
+        self.container.as_ref().filter(|c| !c.closed)
+    }
+
+    // how it's listed in the inventory, with what's inside when it's open: "Pouch (Coin, Key)"
+    pub fn label(&self) -> String {
+        match self.open_container() {
+            Some(container) if !container.items.is_empty() => {
+                let inside: Vec<String> = container.items.iter().map(|i| i.label()).collect();
+                format!("{} ({})", self.name, inside.join(", "))
+            }
+            _ => self.name.clone(),
+        }
+    }
+}
+
+impl Container {
+    // what looking inside shows
+    pub fn describe(&self) -> String {
+        if self.locked {
+            "It's locked.".to_string()
+        } else if self.closed {
+            "It's closed.".to_string()
+        } else if self.items.is_empty() {
+            "It's empty.".to_string()
+        } else {
+            let inside: Vec<&str> = self.items.iter().map(|i| i.name.as_str()).collect();
+            format!("Inside: {}.", inside.join(", "))
+        }
+    }
+}
+
+// Every item in a list plus everything in the open containers among them, all the way down.
+// Whatever's in a closed container is out of reach until it's opened
+pub fn reachable(items: &[Item]) -> Vec<&Item> {
+    let mut found = Vec::new();
+    for item in items {
+        found.push(item);
+        if let Some(container) = item.open_container() {
+            found.extend(reachable(&container.items));
+        }
+    }
+    found
+}
+
+// A reachable item by name, see reachable
+pub fn find_mut<'a>(items: &'a mut [Item], name: &str) -> Option<&'a mut Item> {
+    if let Some(index) = items.iter().position(|i| i.name == name) {
+        return Some(&mut items[index]);
+    }
+    items
+        .iter_mut()
+        .filter_map(|i| i.container.as_mut().filter(|c| !c.closed))
+        .find_map(|c| find_mut(&mut c.items, name))
+}
+
+// is an item in the list or in any container in it, open or not
+pub fn contains(items: &[Item], name: &str) -> bool {
+    items
+        .iter()
+        .any(|i| i.name == name || i.container.as_ref().is_some_and(|c| contains(&c.items, name)))
+}
+
+// Takes an item out of the list, or out of whatever container in it has one, open or not
+pub fn remove(items: &mut Vec<Item>, name: &str) -> Option<Item> {
+    if let Some(index) = items.iter().position(|i| i.name == name) {
+        return Some(items.remove(index));
+    }
+    items
+        .iter_mut()
+        .filter_map(|i| i.container.as_mut())
+        .find_map(|c| remove(&mut c.items, name))
+}
@@ -0,0 +1,148 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    quests::QuestStatus,
+    save,
+    session::{Mode, Session},
+};
+
+/* DEATH
+ * Checked after every command: once the player is at 0 HP or below, however it happened (a fight,
+ * eating the wrong thing, a trap), the world's death rules decide what happens next.
+ *
+ *   restart     a new game from the start of the world
+ *   reload      back to the last save made or loaded this session, or a new game if there isn't one
+ *   respawn     back at the last checkpoint room (or the start room) with some HP, minus some XP,
+ *               and maybe without the inventory, which stays where the player fell
+ *   permadeath  that's it, the game is over
+ *
+ * Either way the player gets a summary of how the run went.
+ */
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeathPolicy {
+    #[default]
+    Restart,
+    Reload,
+    Respawn,
+    Permadeath,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeathRules {
+    pub policy: DeathPolicy,
+    pub respawn_hp: i32,  // hp the player comes back with
+    pub xp_penalty: u32,  // percent of the xp towards the next level lost on respawn
+    pub drop_items: bool, // respawning leaves the inventory (not the equipment) where the player died
+}
+
+impl Default for DeathRules {
+    fn default() -> Self {
+        DeathRules {
+            policy: DeathPolicy::Restart,
+            respawn_hp: 50,
+            xp_penalty: 50,
+            drop_items: true,
+        }
+    }
+}
+
+// Does whatever the world's death rules say and returns what to tell the player
+pub fn die(session: &mut Session) -> String {
+    let mut lines = vec!["*** You have died. ***".to_string(), summary(session)];
+
+    let mut policy = session.world.death.policy;
+    if policy == DeathPolicy::Reload && session.last_save.is_none() {
+        lines.push("There's no save to go back to.".to_string());
+        policy = DeathPolicy::Restart;
+    }
+    if policy == DeathPolicy::Restart && session.fresh_world.is_none() {
+        policy = DeathPolicy::Respawn; // a game loaded from outside doesn't know how it started
+    }
+
+    match policy {
+        DeathPolicy::Restart => {
+            let world = session.fresh_world.as_deref().cloned().expect("checked above");
+            let seed = session.rng.next_u64();
+            let last_save = session.last_save.take();
+            *session = Session::new(world, seed);
+            session.last_save = last_save;
+            let room = &session.world.rooms[&session.player.current_room];
+            lines.push(format!("You wake up at the beginning, as if none of it happened.\n{}", room.description));
+        }
+        DeathPolicy::Reload => {
+            let data = session.last_save.clone().expect("checked above");
+            match save::from_str(&data) {
+                Ok(saved) => {
+                    let fresh_world = session.fresh_world.take();
+                    *session = saved;
+                    session.fresh_world = fresh_world;
+                    session.last_save = Some(data);
+                    lines.push("You wake up where you last saved.".to_string());
+                }
+                Err(err) => {
+                    session.mode = Mode::GameOver;
+                    lines.push(format!("The last save couldn't be loaded ({}). Game over.", err));
+                }
+            }
+        }
+        DeathPolicy::Respawn => lines.push(respawn(session)),
+        DeathPolicy::Permadeath => {
+            session.mode = Mode::GameOver;
+            lines.push("There are no second chances here. GAME OVER.".to_string());
+        }
+    }
+    lines.join("\n")
+}
+
+// back on their feet at the last checkpoint, paying for it
+fn respawn(session: &mut Session) -> String {
+    let rules = session.world.death.clone();
+    let player = &mut session.player;
+    let world = &mut session.world;
+    let mut lines = Vec::new();
+
+    let lost = player.xp * rules.xp_penalty.min(100) / 100;
+    player.xp -= lost;
+    if lost > 0 {
+        lines.push(format!("You lost {} XP.", lost));
+    }
+    if rules.drop_items && !player.inventory.is_empty() {
+        let dropped = std::mem::take(&mut player.inventory);
+        world.room_mut(&player.current_room).items.extend(dropped);
+        lines.push("Everything you were carrying is still where you fell.".to_string());
+    }
+
+    let checkpoint = player.checkpoint.clone().unwrap_or_else(|| world.start_room.clone());
+    player.current_room = checkpoint.clone();
+    player.visited.insert(checkpoint.clone());
+    player.health_points = rules.respawn_hp.max(1);
+    session.mode = Mode::Explore;
+
+    let room = &world.rooms[&checkpoint];
+    lines.insert(0, format!("You wake up in {} with {} HP.", room.name, player.health_points));
+    lines.join("\n")
+}
+
+// how the run went, shown on death
+pub fn summary(session: &Session) -> String {
+    let player = &session.player;
+    let world = &session.world;
+    let done = world.quests.iter().filter(|q| q.status == QuestStatus::Completed).count();
+    format!(
+        "=== HOW IT WENT ===\n\
+        Turns: {}   Level: {}   XP: {}/{}\n\
+        Rooms explored: {}/{}\n\
+        Enemies defeated: {}\n\
+        Quests completed: {}/{}",
+        session.turn,
+        player.level,
+        player.xp,
+        player.xp_to_next_level(),
+        player.visited.len(),
+        world.rooms.len(),
+        world.npcs.iter().filter(|n| n.dead).count(),
+        done,
+        world.quests.len()
+    )
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    player::Player,
+    world::{Item, NpcId, RoomId, World},
+};
+
+/* DIALOGUE TREES
+ * A conversation is a graph of nodes. Each node is something the npc says plus a numbered list of
+ * responses the player can pick from. A response can lead to another node or end the conversation,
+ * and can be gated on (or change) world flags and the player's inventory.
+ * Npcs keep their trees by room just like all_dialogues, so the same npc can have a different
+ * conversation after it moves. Rooms without a tree fall back to the plain dialogue lines.
+ */
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DialogueTree {
+    pub start: String, // id of the first node
+    pub nodes: HashMap<String, DialogueNode>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DialogueNode {
+    pub text: String,
+    pub responses: Vec<DialogueResponse>, // no responses means the conversation ends after this node
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DialogueResponse {
+    pub text: String,
+    pub next: Option<String>, // node to go to, None ends the conversation
+    pub requires_flags: Vec<String>, // only shown if all of these flags are set
+    pub unless_flags: Vec<String>,   // hidden if any of these flags are set
+    pub requires_items: Vec<String>, // only shown if the player is carrying all of these
+    pub set_flags: Vec<String>,
+    pub take_items: Vec<String>, // removed from the player's inventory when picked
+    pub give_items: Vec<Item>,   // added to the player's inventory when picked
+}
+
+impl DialogueResponse {
+    pub fn is_available(&self, player: &Player, world: &World) -> bool {
+        self.requires_flags.iter().all(|f| world.get_flag(f))
+            && !self.unless_flags.iter().any(|f| world.get_flag(f))
+            && self
+                .requires_items
+                .iter()
+                .all(|name| player.has_item(name))
+    }
+}
+
+// what can be typed while talking, for help
+pub const HELP: &[(&str, &str)] = &[("<number>", "Pick one of the responses."), ("bye, leave", "End the conversation.")];
+
+// A conversation in progress, stored in the session while the player is talking (see Mode::Dialogue)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Conversation {
+    pub npc: NpcId,
+    pub room: RoomId,         // which of the npc's trees we're in
+    pub node: Option<String>, // None once the conversation is over
+}
+
+impl Conversation {
+    // Starts talking to an npc that has a tree for its current room, returns the first node's text.
+    pub fn start(npc: &NpcId, player: &Player, world: &World) -> Option<(Conversation, String)> {
+        let npc = world.npc(npc)?;
+        let tree = npc.conversations.get(&npc.current_room)?;
+        let mut conversation = Conversation {
+            npc: npc.id.clone(),
+            room: npc.current_room.clone(),
+            node: Some(tree.start.clone()),
+        };
+        let text = conversation.render(player, world);
+        Some((conversation, text))
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.node.is_none()
+    }
+
+    fn tree<'a>(&self, world: &'a World) -> Option<&'a DialogueTree> {
+        world.npc(&self.npc)?.conversations.get(&self.room)
+    }
+
+    fn current_node<'a>(&self, world: &'a World) -> Option<&'a DialogueNode> {
+        self.tree(world)?.nodes.get(self.node.as_ref()?)
+    }
+
+    fn available_responses<'a>(&self, player: &Player, world: &'a World) -> Vec<&'a DialogueResponse> {
+        self.current_node(world)
+            .map(|node| {
+                node.responses
+                    .iter()
+                    .filter(|r| r.is_available(player, world))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // what the npc is saying right now and the numbered responses. ends the conversation if
+    // there's nothing left for the player to say.
+    fn render(&mut self, player: &Player, world: &World) -> String {
+        let (Some(node), Some(npc)) = (self.current_node(world), world.npc(&self.npc)) else {
+            self.node = None;
+            return "[End of conversation]".to_string();
+        };
+
+        let mut lines = vec![format!("{}: {}", npc.name, node.text)];
+        let responses = self.available_responses(player, world);
+        if responses.is_empty() {
+            self.node = None;
+            lines.push("[End of conversation]".to_string());
+        } else {
+            for (i, response) in responses.iter().enumerate() {
+                lines.push(format!("  {}. {}", i + 1, response.text));
+            }
+            lines.push("Choose a response (number), or 'bye' to leave.".to_string());
+        }
+        lines.join("\n")
+    }
+
+    // the prompt again, for when a game is loaded mid conversation
+    pub fn prompt(&self, player: &Player, world: &World) -> String {
+        self.clone().render(player, world)
+    }
+
+    // Feeds one line of player input (a response number) into the conversation
+    pub fn handle_input(&mut self, input: &str, player: &mut Player, world: &mut World) -> String {
+        let input = input.trim().to_lowercase();
+        if input == "bye" || input == "leave" {
+            self.node = None;
+            return "[End of conversation]".to_string();
+        }
+
+        let responses = self.available_responses(player, world);
+        let choice = match input.parse::<usize>() {
+            Ok(choice) if choice > 0 && choice <= responses.len() => responses[choice - 1].clone(),
+            _ => {
+                return format!(
+                    "Please choose a response between 1 and {}, or 'bye' to leave.",
+                    responses.len()
+                )
+            }
+        };
+
+        let mut lines = Vec::new();
+        for flag in &choice.set_flags {
+            world.set_flag(flag, true);
+        }
+        for name in &choice.take_items {
+            player.unequip_item(name); // anything being worn comes off first
+            if player.remove_item(name).is_some() {
+                lines.push(format!("You hand over the {}.", name));
+            }
+        }
+        for item in &choice.give_items {
+            lines.push(format!("You received the {}.", item.name));
+            player.take_item(item.clone());
+        }
+
+        self.node = choice.next.clone();
+        if self.node.is_some() {
+            lines.push(self.render(player, world));
+        } else {
+            lines.push("[End of conversation]".to_string());
+        }
+        lines.join("\n")
+    }
+}
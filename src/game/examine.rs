@@ -0,0 +1,128 @@
+use crate::game::{
+    containers,
+    player::Player,
+    world::{Item, Npc, Scenery, World},
+};
+
+/* EXAMINE
+ * Everything `examine <thing>` can look at: items in the room, carried or equipped, npcs standing
+ * in the room and the room's scenery, plus whatever is in an open container. Each one shows its
+ * description plus whatever about it is worth knowing right now, like what an item does once the
+ * player has found out, what's inside a chest or how hurt an npc looks.
+ */
+pub enum Thing<'a> {
+    Item(&'a Item, Place),
+    Npc(&'a Npc),
+    Scenery(&'a Scenery),
+}
+
+// where an item is, since that changes what there is to say about it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Place {
+    Here,
+    Carried,
+    Worn,
+}
+
+// Everything the player can see or is holding, the things they're holding first. Whatever's in an
+// open container counts as being wherever the container is
+pub fn visible_things<'a>(player: &'a Player, world: &'a World) -> Vec<Thing<'a>> {
+    let room = &world.rooms[&player.current_room];
+    let mut things: Vec<Thing> = Vec::new();
+    things.extend(containers::reachable(&player.inventory).into_iter().map(|i| Thing::Item(i, Place::Carried)));
+    things.extend(player.equipment.values().map(|i| Thing::Item(i, Place::Worn)));
+    things.extend(containers::reachable(&room.items).into_iter().map(|i| Thing::Item(i, Place::Here)));
+    things.extend(world.npcs_in(&player.current_room).map(Thing::Npc));
+    things.extend(room.scenery.iter().map(Thing::Scenery));
+    things
+}
+
+impl Thing<'_> {
+    pub fn name_and_description(&self) -> (&str, &str) {
+        match self {
+            Thing::Item(item, _) => (&item.name, &item.description),
+            Thing::Npc(npc) => (&npc.name, &npc.description),
+            Thing::Scenery(scenery) => (&scenery.name, &scenery.description),
+        }
+    }
+
+    pub fn describe(&self, player: &Player) -> String {
+        match self {
+            Thing::Item(item, place) => describe_item(item, *place, player),
+            Thing::Npc(npc) => describe_npc(npc),
+            Thing::Scenery(scenery) => scenery.description.clone(),
+        }
+    }
+}
+
+fn describe_item(item: &Item, place: Place, player: &Player) -> String {
+    let mut lines = vec![if item.description.is_empty() {
+        format!("There's nothing special about the {}.", item.name)
+    } else {
+        item.description.clone()
+    }];
+
+    if let Some(slot) = item.slot {
+        let mut bonuses = Vec::new();
+        if item.attack_increase_amount != 0 {
+            bonuses.push(format!("{:+} AP", item.attack_increase_amount));
+        }
+        if item.defense_increase_amount != 0 {
+            bonuses.push(format!("{:+} DEF", item.defense_increase_amount));
+        }
+        let bonuses = if bonuses.is_empty() { String::new() } else { format!(", {}", bonuses.join(" ")) };
+        lines.push(match place {
+            Place::Worn => format!("You have it equipped ({}{}).", slot, bonuses),
+            _ => format!("It can be equipped ({}{}).", slot, bonuses),
+        });
+    }
+
+    if item.can_eat {
+        // the player only knows what eating it does after they've tried one
+        lines.push(if !player.identified.contains(&item.name) {
+            "It looks edible, but who knows what it would do to you.".to_string()
+        } else if item.heal_amount > 0 {
+            format!("Eating it heals {} HP.", item.heal_amount)
+        } else if item.heal_amount < 0 {
+            format!("Eating it hurts, {} HP.", item.heal_amount)
+        } else {
+            "Eating it does nothing.".to_string()
+        });
+    }
+
+    if let Some(container) = &item.container {
+        lines.push(container.describe());
+    }
+
+    if place == Place::Here && !item.can_take {
+        lines.push("It can't be taken.".to_string());
+    }
+    lines.join("\n")
+}
+
+fn describe_npc(npc: &Npc) -> String {
+    let mut lines = Vec::new();
+    if !npc.description.is_empty() {
+        lines.push(npc.description.clone());
+    }
+
+    // how they look, not the actual numbers
+    let health = npc.health_points as f32 / npc.max_health_points.max(1) as f32;
+    lines.push(format!(
+        "{} {}.",
+        npc.name,
+        if health >= 1.0 {
+            "looks unhurt"
+        } else if health >= 0.6 {
+            "has a few scratches"
+        } else if health >= 0.25 {
+            "is badly hurt"
+        } else {
+            "can barely stand"
+        }
+    ));
+    if npc.hostile {
+        lines.push(format!("{} looks ready to fight.", npc.name));
+    }
+    lines.join("\n")
+}
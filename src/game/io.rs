@@ -0,0 +1,126 @@
+use std::collections::VecDeque;
+
+/* GAME I/O
+ * The engine never touches stdin/stdout directly, it goes through a GameIo instead.
+ * That way the same combat/prompt code works in the terminal, in a browser (wasm) and in tests.
+ */
+pub trait GameIo {
+    // Reads one line of player input without the trailing newline. None means there is no more input.
+    fn read_line(&mut self) -> Option<String>;
+
+    // Writes text exactly as given, no newline added
+    fn write(&mut self, text: &str);
+
+    fn write_line(&mut self, text: &str) {
+        self.write(text);
+        self.write("\n");
+    }
+
+    // Writes a prompt and reads the answer
+    fn prompt(&mut self, text: &str) -> Option<String> {
+        self.write(text);
+        self.read_line()
+    }
+}
+
+// Regular stdin/stdout, used by the cli binary
+#[cfg(feature = "cli")]
+#[derive(Debug, Default)]
+pub struct TerminalIo;
+
+#[cfg(feature = "cli")]
+impl GameIo for TerminalIo {
+    fn read_line(&mut self) -> Option<String> {
+        let mut input = String::new();
+        match std::io::stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => None, // eof (ctrl-d) or a broken stdin
+            Ok(_) => Some(input.trim_end_matches(['\r', '\n']).to_string()),
+        }
+    }
+
+    fn write(&mut self, text: &str) {
+        use std::io::Write;
+        print!("{}", text);
+        std::io::stdout().flush().unwrap();
+    }
+}
+
+// Input is a script of lines queued up ahead of time, output is collected into a string.
+// Useful for tests and for frontends that hand the engine whatever the player typed.
+#[derive(Debug, Default, Clone)]
+pub struct ScriptedIo {
+    input: VecDeque<String>,
+    output: String,
+}
+
+impl ScriptedIo {
+    pub fn new<I, S>(lines: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        ScriptedIo {
+            input: lines.into_iter().map(Into::into).collect(),
+            output: String::new(),
+        }
+    }
+
+    // queue another line of input
+    pub fn push_input(&mut self, line: &str) {
+        self.input.push_back(line.to_string());
+    }
+
+    pub fn output(&self) -> &str {
+        &self.output
+    }
+
+    // hands back everything written so far and clears it
+    pub fn take_output(&mut self) -> String {
+        std::mem::take(&mut self.output)
+    }
+}
+
+impl GameIo for ScriptedIo {
+    fn read_line(&mut self) -> Option<String> {
+        self.input.pop_front()
+    }
+
+    fn write(&mut self, text: &str) {
+        self.output.push_str(text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::testing;
+
+    #[test]
+    fn prompt_reads_queued_lines_in_order() {
+        let mut io = ScriptedIo::new(["first"]);
+        io.push_input("second");
+        assert_eq!(io.prompt("? ").as_deref(), Some("first"));
+        assert_eq!(io.read_line().as_deref(), Some("second"));
+        assert_eq!(io.read_line(), None);
+        assert_eq!(io.output(), "? ");
+    }
+
+    #[test]
+    fn take_output_clears_it() {
+        let mut io = ScriptedIo::default();
+        io.write_line("hello");
+        assert_eq!(io.take_output(), "hello\n");
+        assert_eq!(io.output(), "");
+    }
+
+    #[test]
+    fn drives_a_game() {
+        let mut io = ScriptedIo::new(["take key", "n", "inventory"]);
+        testing::play(testing::WORLD, 1, &mut io);
+        let output = io.take_output();
+        assert!(output.contains("You picked up the Key."), "{}", output);
+        assert!(output.contains("You moved to: Room B."), "{}", output);
+        assert!(output.contains(r#"You are carrying: ["Key"]"#), "{}", output);
+        assert!(output.ends_with("> "), "every line is prompted for, the last prompt gets no answer");
+    }
+}
@@ -0,0 +1,802 @@
+use std::{collections::HashMap, fmt, fs, ops::Range, path::Path};
+
+use serde::{
+    de::{self, value::MapAccessDeserializer, MapAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use toml::Spanned;
+
+use crate::game::{
+    death::{DeathPolicy, DeathRules},
+    dialogue::{DialogueNode, DialogueResponse, DialogueTree},
+    quests::{Goal, Objective, Quest, QuestStatus, Rewards},
+    skills::{Skill, SkillEffect},
+    triggers::{Action, Condition, Trigger},
+    world::{Container, Door, Exit, Item, NpcId, RoomId, Scenery, Slot, Trade, World},
+};
+
+/* WORLD FILE FORMAT
+ * ==================================================================================================================
+ * Worlds are plain TOML files, so a new game is just a new data file instead of a fork of the engine.
+ * Every room and npc has a unique string id, and everything else (exits, npc rooms, quests, triggers)
+ * refers to them by that id. Order in the file doesn't matter.
+ *
+ *   start_room = "start"                    # id of the room the player starts in
+ *
+ *   [[rooms]]
+ *   id = "start"
+ *   name = "Starting Room"
+ *   description = "You are in the starting room."
+ *   exits = { north = "maze_entrance" }     # direction/string -> room id
+ *   position = [0, 0]                       # optional spot on the map, x grows east and y grows south.
+ *                                           # rooms without one are placed by following north/south/east/west exits
+ *   checkpoint = true                       # optional, entering it makes it where the player respawns
+ *
+ *   # exits can also be tables, everything but `to` is optional:
+ *   #   down = { to = "cellar", door = { locked = true, key = "Key" } }
+ *   #   door = { ... }              a door, closed unless open = true. locked ones need `unlock <dir> with <key>`
+ *   #   hidden = true               not shown or usable until a reveal_exit action
+ *   #   requires_flag = "lights_on" can't be used until this flag is set...
+ *   #   blocked_message = "..."     ...and this is what the player is told instead
+ *
+ *   [[rooms.items]]                         # any number of items per room
+ *   name = "Key"
+ *   description = "A small rusty key."
+ *   can_take = true                         # optional, default true. false for things that stay put, like a chest
+ *   can_eat = false                         # every flag and amount below is optional (false / 0)
+ *   slot = "weapon"                         # optional, makes it equippable: weapon, offhand, head, body or accessory
+ *   heal_amount = 0
+ *   attack_increase_amount = 0
+ *   defense_increase_amount = 0
+ *
+ *   [rooms.items.container]                 # optional, makes it something other items go in (a chest, a bag)
+ *   open = false                            # optional, closed unless open = true
+ *   locked = true                           # optional, locked ones need `unlock <item> with <key>`
+ *   key = "Key"                             # optional, the item that unlocks it
+ *
+ *   [[rooms.items.container.items]]         # what's inside, same fields as any item. containers can go in containers
+ *   name = "Coin"
+ *   description = "An old coin."
+ *
+ *   [[rooms.scenery]]                       # things that can be examined but not taken
+ *   name = "Carvings"
+ *   description = "Someone scratched a map into the wall. Most of it has worn away."
+ *
+ *   [[npcs]]
+ *   id = "merlin"
+ *   name = "Merlin"
+ *   description = "An old wizard in a dusty robe."   # optional, shown by `examine`
+ *   room = "maze_entrance"                  # room id the npc starts in
+ *   health_points = 20
+ *   attack_power = 20
+ *   hostile = false
+ *   xp = 40                                 # optional, xp for defeating them. defaults to health_points + attack_power
+ *
+ *   [[npcs.inventory]]                      # items they carry, dropped when defeated. same fields as room items
+ *   name = "Wizard Charm"
+ *   description = "It hums faintly."
+ *
+ *   [[npcs.loot]]                           # items only dropped when defeated
+ *   name = "Bone"
+ *   description = "What's left of them."
+ *
+ *   [[npcs.trades]]                         # `give <item> to <npc>`, friendly npcs only
+ *   wants = "Bone"                          # the item they'll take
+ *   gives = "Wizard Charm"                  # optional, an item from their inventory they give back
+ *   reply = "Just what I needed."           # optional
+ *
+ *   [[npcs.dialogues]]                      # one entry per room the npc has something to say in
+ *   room = "maze_entrance"
+ *   lines = ["Greetings Traveler.", "Good luck on your quest."]
+ *
+ *   [[npcs.conversations]]                  # branching conversation, used instead of lines in this room
+ *   room = "corridor"
+ *   start = "hello"                         # id of the first node
+ *
+ *   [npcs.conversations.nodes.hello]
+ *   text = "Hello again traveler."
+ *
+ *   [[npcs.conversations.nodes.hello.responses]]
+ *   text = "Got anything for me?"           # what the player says, picked by number
+ *   next = "gift"                           # node to go to, leave it out to end the conversation
+ *   requires_flags = ["skeleton_dead"]      # all optional: only show if these flags are set
+ *   unless_flags = ["got_gift"]             #   hide if any of these are set
+ *   requires_items = ["Key"]                #   only show if the player carries these
+ *   set_flags = ["got_gift"]                #   set these flags when picked
+ *   take_items = ["Key"]                    #   take these from the player when picked
+ *
+ *   [[npcs.conversations.nodes.hello.responses.give_items]]   # items handed to the player when picked
+ *   name = "Healing Grass"
+ *   description = "Heals a small amount of health."
+ *   can_eat = true
+ *   heal_amount = 20
+ *
+ *   [[quests]]
+ *   id = "clear_the_maze"
+ *   name = "Clear the Maze"
+ *   description = "Merlin wants the skeleton gone."
+ *   start_flag = "merlin_gift"              # optional, quest starts once this flag is set. no flag = active from the start
+ *
+ *   [[quests.objectives]]                   # done in order, each one has a description and exactly one goal:
+ *   description = "Defeat the Skeleton"     #   reach_room = "corridor", obtain_item = "Key", defeat_npc = "skeleton",
+ *   defeat_npc = "skeleton"                 #   talk_to = "merlin" or set_flag = "some_flag"
+ *
+ *   [quests.rewards]                        # all optional
+ *   health_points = 10
+ *   attack_power = 5
+ *   xp = 50
+ *   flags = ["maze_cleared"]
+ *
+ *   [[quests.rewards.items]]
+ *   name = "Amulet"
+ *   description = "A reward for a job well done."
+ *
+ *   [[triggers]]                            # checked after every command
+ *   id = "merlin_moves_on"
+ *   once = true                             # optional, default true. false = fire every command the conditions hold
+ *   conditions = [                          # all must hold
+ *       { npc_dead = "skeleton" },          # also: flag_set, flag_unset, player_in_room, has_item, turn_at_least
+ *   ]
+ *   actions = [                             # run in order
+ *       { move_npc = { npc = "merlin", room = "corridor" } },
+ *       { message = "You hear footsteps heading east." },
+ *       # also: set_flag = "x", clear_flag = "x", remove_item = { room = "start", item = "Key" },
+ *       #       open_exit = { room = "maze_1", direction = "down", to = "cellar" },
+ *       #       reveal_exit = { room = "start", direction = "down" },
+ *       #       unlock_exit = { room = "start", direction = "down" },   (unlocks and opens the door)
+ *       #       spawn_item = { room = "maze_1", item = { name = "Bone", description = "Old." } }
+ *   ]
+ *
+ *   [[skills]]                              # learned once the player reaches `level`, used in combat with [S]kill
+ *   name = "Power_Strike"
+ *   description = "A heavy swing."
+ *   level = 1                               # optional, default 1 (known from the start)
+ *   cooldown = 2                            # optional, rounds before it can be used again
+ *   effect = { damage = 2.0 }               # one of: damage = <AP multiplier>, heal = <hp>, stun = <enemy turns>
+ *
+ *   [death]                                 # optional, what happens when the player dies
+ *   policy = "respawn"                      # restart (default), reload (the last save), respawn or permadeath
+ *   respawn_hp = 50                         # the rest only matter for respawn: hp the player comes back with,
+ *   xp_penalty = 50                         #   percent of their xp they lose,
+ *   drop_items = true                       #   and whether their inventory stays where they died
+ */
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorldFile {
+    start_room: Spanned<RoomId>,
+    #[serde(default)]
+    rooms: Vec<RoomDef>,
+    #[serde(default)]
+    npcs: Vec<NpcDef>,
+    #[serde(default)]
+    quests: Vec<QuestDef>,
+    #[serde(default)]
+    triggers: Vec<TriggerDef>,
+    #[serde(default)]
+    skills: Vec<SkillDef>,
+    #[serde(default)]
+    death: DeathDef,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeathDef {
+    #[serde(default)]
+    policy: DeathPolicy,
+    respawn_hp: Option<i32>,
+    xp_penalty: Option<u32>,
+    drop_items: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RoomDef {
+    id: Spanned<RoomId>,
+    name: String,
+    description: String,
+    #[serde(default)]
+    items: Vec<ItemDef>,
+    #[serde(default)]
+    exits: HashMap<String, ExitDef>,
+    position: Option<(i32, i32)>,
+    #[serde(default)]
+    scenery: Vec<SceneryDef>,
+    #[serde(default)]
+    checkpoint: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneryDef {
+    name: String,
+    description: String,
+}
+
+// an exit is either just the id of the room it leads to, or a table with more to it
+#[derive(Debug)]
+enum ExitDef {
+    To(RoomId),
+    Full(FullExitDef),
+}
+
+// by hand instead of #[serde(untagged)], which turns every mistake inside an exit table into
+// "did not match any variant". this way a typo like `doro` is reported by name
+impl<'de> Deserialize<'de> for ExitDef {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ExitVisitor;
+
+        impl<'de> Visitor<'de> for ExitVisitor {
+            type Value = ExitDef;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a room id or an exit table")
+            }
+
+            fn visit_str<E: de::Error>(self, id: &str) -> Result<ExitDef, E> {
+                Ok(ExitDef::To(RoomId::from(id)))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<ExitDef, A::Error> {
+                FullExitDef::deserialize(MapAccessDeserializer::new(map)).map(ExitDef::Full)
+            }
+        }
+
+        deserializer.deserialize_any(ExitVisitor)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FullExitDef {
+    to: RoomId,
+    door: Option<DoorDef>,
+    #[serde(default)]
+    hidden: bool,
+    requires_flag: Option<String>,
+    blocked_message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DoorDef {
+    #[serde(default)]
+    open: bool,
+    #[serde(default)]
+    locked: bool,
+    key: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ItemDef {
+    name: String,
+    description: String,
+    #[serde(default = "default_can_take")]
+    can_take: bool,
+    #[serde(default)]
+    can_eat: bool,
+    slot: Option<Slot>,
+    #[serde(default)]
+    heal_amount: i32,
+    #[serde(default)]
+    attack_increase_amount: i32,
+    #[serde(default)]
+    defense_increase_amount: i32,
+    container: Option<ContainerDef>,
+}
+
+// most items are there to be picked up (loot, rewards, spawned items...), the ones that aren't say so
+fn default_can_take() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ContainerDef {
+    #[serde(default)]
+    open: bool,
+    #[serde(default)]
+    locked: bool,
+    key: Option<String>,
+    #[serde(default)]
+    items: Vec<ItemDef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NpcDef {
+    id: Spanned<NpcId>,
+    name: String,
+    #[serde(default)]
+    description: String,
+    room: RoomId,
+    health_points: i32,
+    attack_power: i32,
+    xp: Option<u32>,
+    #[serde(default)]
+    hostile: bool,
+    #[serde(default)]
+    dialogues: Vec<DialogueDef>,
+    #[serde(default)]
+    conversations: Vec<ConversationDef>,
+    #[serde(default)]
+    inventory: Vec<ItemDef>,
+    #[serde(default)]
+    loot: Vec<ItemDef>,
+    #[serde(default)]
+    trades: Vec<TradeDef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TradeDef {
+    wants: String,
+    gives: Option<String>,
+    reply: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DialogueDef {
+    room: RoomId,
+    lines: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConversationDef {
+    room: RoomId,
+    start: Spanned<String>,
+    nodes: HashMap<String, NodeDef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NodeDef {
+    text: String,
+    #[serde(default)]
+    responses: Vec<ResponseDef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ResponseDef {
+    text: String,
+    #[serde(default)]
+    next: Option<Spanned<String>>,
+    #[serde(default)]
+    requires_flags: Vec<String>,
+    #[serde(default)]
+    unless_flags: Vec<String>,
+    #[serde(default)]
+    requires_items: Vec<String>,
+    #[serde(default)]
+    set_flags: Vec<String>,
+    #[serde(default)]
+    take_items: Vec<String>,
+    #[serde(default)]
+    give_items: Vec<ItemDef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QuestDef {
+    id: String,
+    name: String,
+    description: String,
+    #[serde(default)]
+    start_flag: Option<String>,
+    objectives: Vec<Spanned<ObjectiveDef>>,
+    #[serde(default)]
+    rewards: RewardsDef,
+}
+
+// one goal per objective, the field name says which kind it is
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ObjectiveDef {
+    description: String,
+    reach_room: Option<RoomId>,
+    obtain_item: Option<String>,
+    defeat_npc: Option<NpcId>,
+    talk_to: Option<NpcId>,
+    set_flag: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RewardsDef {
+    #[serde(default)]
+    items: Vec<ItemDef>,
+    #[serde(default)]
+    health_points: i32,
+    #[serde(default)]
+    attack_power: i32,
+    #[serde(default)]
+    xp: u32,
+    #[serde(default)]
+    flags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SkillDef {
+    name: String,
+    description: String,
+    #[serde(default = "default_skill_level")]
+    level: u32,
+    #[serde(default)]
+    cooldown: u32,
+    effect: SkillEffect,
+}
+
+fn default_skill_level() -> u32 {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TriggerDef {
+    id: String,
+    #[serde(default = "default_once")]
+    once: bool,
+    #[serde(default)]
+    conditions: Vec<Condition>,
+    actions: Vec<ActionDef>,
+}
+
+fn default_once() -> bool {
+    true
+}
+
+// same as triggers::Action, but spawned items are written like any other item in the file
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum ActionDef {
+    SetFlag(String),
+    ClearFlag(String),
+    MoveNpc { npc: NpcId, room: RoomId },
+    SpawnItem { room: RoomId, item: ItemDef },
+    RemoveItem { room: RoomId, item: String },
+    OpenExit { room: RoomId, direction: String, to: RoomId },
+    RevealExit { room: RoomId, direction: String },
+    UnlockExit { room: RoomId, direction: String },
+    Message(String),
+}
+
+// Anything that can go wrong while loading a world file. Parse errors carry the line/column so
+// whoever is writing the world can find the typo.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadError {
+    Io { path: String, message: String },
+    Parse { line: usize, column: usize, message: String },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io { path, message } => write!(f, "could not read world file {}: {}", path, message),
+            LoadError::Parse { line, column, message } => {
+                write!(f, "world file error at line {}, column {}: {}", line, column, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl LoadError {
+    // turns a byte span from the toml parser into a 1-based line/column
+    fn at(source: &str, span: Option<Range<usize>>, message: &str) -> Self {
+        let offset = span.map(|s| s.start).unwrap_or(0).min(source.len());
+        let before = &source[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+        LoadError::Parse {
+            line,
+            column,
+            message: message.trim().to_string(),
+        }
+    }
+}
+
+impl From<ItemDef> for Item {
+    fn from(def: ItemDef) -> Self {
+        Item {
+            name: def.name,
+            description: def.description,
+            can_take: def.can_take,
+            can_eat: def.can_eat,
+            slot: def.slot,
+            heal_amount: def.heal_amount,
+            attack_increase_amount: def.attack_increase_amount,
+            defense_increase_amount: def.defense_increase_amount,
+            container: def.container.map(|c| Container {
+                items: c.items.into_iter().map(Item::from).collect(),
+                closed: !c.open || c.locked,
+                locked: c.locked,
+                key: c.key,
+            }),
+        }
+    }
+}
+
+impl ConversationDef {
+    // node ids are only known once the whole tree is read, so a typo in `start`/`next` is caught here
+    fn into_tree(self, source: &str) -> Result<DialogueTree, LoadError> {
+        let targets = std::iter::once(&self.start).chain(
+            self.nodes
+                .values()
+                .flat_map(|node| node.responses.iter().filter_map(|r| r.next.as_ref())),
+        );
+        for id in targets {
+            if !self.nodes.contains_key(id.get_ref()) {
+                return Err(LoadError::at(
+                    source,
+                    Some(id.span()),
+                    &format!("conversation has no node called '{}'", id.get_ref()),
+                ));
+            }
+        }
+
+        let nodes = self
+            .nodes
+            .into_iter()
+            .map(|(id, node)| {
+                let responses = node
+                    .responses
+                    .into_iter()
+                    .map(|r| DialogueResponse {
+                        text: r.text,
+                        next: r.next.map(Spanned::into_inner),
+                        requires_flags: r.requires_flags,
+                        unless_flags: r.unless_flags,
+                        requires_items: r.requires_items,
+                        set_flags: r.set_flags,
+                        take_items: r.take_items,
+                        give_items: r.give_items.into_iter().map(Item::from).collect(),
+                    })
+                    .collect();
+                (id, DialogueNode { text: node.text, responses })
+            })
+            .collect();
+
+        Ok(DialogueTree {
+            start: self.start.into_inner(),
+            nodes,
+        })
+    }
+}
+
+impl From<ExitDef> for Exit {
+    fn from(def: ExitDef) -> Self {
+        match def {
+            ExitDef::To(room) => Exit::to(room),
+            ExitDef::Full(def) => Exit {
+                to: def.to,
+                // doors start closed unless they say otherwise, and a locked door is never open
+                door: def.door.map(|door| Door {
+                    closed: !door.open || door.locked,
+                    locked: door.locked,
+                    key: door.key,
+                }),
+                hidden: def.hidden,
+                requires_flag: def.requires_flag,
+                blocked_message: def.blocked_message,
+            },
+        }
+    }
+}
+
+impl From<ActionDef> for Action {
+    fn from(def: ActionDef) -> Self {
+        match def {
+            ActionDef::SetFlag(flag) => Action::SetFlag(flag),
+            ActionDef::ClearFlag(flag) => Action::ClearFlag(flag),
+            ActionDef::MoveNpc { npc, room } => Action::MoveNpc { npc, room },
+            ActionDef::SpawnItem { room, item } => Action::SpawnItem {
+                room,
+                item: item.into(),
+            },
+            ActionDef::RemoveItem { room, item } => Action::RemoveItem { room, item },
+            ActionDef::OpenExit { room, direction, to } => Action::OpenExit { room, direction, to },
+            ActionDef::RevealExit { room, direction } => Action::RevealExit { room, direction },
+            ActionDef::UnlockExit { room, direction } => Action::UnlockExit { room, direction },
+            ActionDef::Message(text) => Action::Message(text),
+        }
+    }
+}
+
+impl From<TriggerDef> for Trigger {
+    fn from(def: TriggerDef) -> Self {
+        Trigger {
+            id: def.id,
+            conditions: def.conditions,
+            actions: def.actions.into_iter().map(Action::from).collect(),
+            once: def.once,
+            fired: false,
+        }
+    }
+}
+
+impl QuestDef {
+    fn into_quest(self, source: &str) -> Result<Quest, LoadError> {
+        let mut objectives = Vec::new();
+        for def in self.objectives {
+            let span = def.span();
+            let def = def.into_inner();
+            let mut goals = Vec::new();
+            goals.extend(def.reach_room.map(Goal::ReachRoom));
+            goals.extend(def.obtain_item.map(Goal::ObtainItem));
+            goals.extend(def.defeat_npc.map(Goal::DefeatNpc));
+            goals.extend(def.talk_to.map(Goal::TalkTo));
+            goals.extend(def.set_flag.map(Goal::SetFlag));
+            if goals.len() != 1 {
+                return Err(LoadError::at(
+                    source,
+                    Some(span),
+                    "a quest objective needs exactly one of reach_room, obtain_item, defeat_npc, talk_to or set_flag",
+                ));
+            }
+            objectives.push(Objective {
+                description: def.description,
+                goal: goals.remove(0),
+            });
+        }
+
+        // quests without a start flag are already going when the game starts
+        let status = if self.start_flag.is_none() {
+            QuestStatus::Active { step: 0 }
+        } else {
+            QuestStatus::Inactive
+        };
+
+        Ok(Quest {
+            id: self.id,
+            name: self.name,
+            description: self.description,
+            start_flag: self.start_flag,
+            objectives,
+            rewards: Rewards {
+                items: self.rewards.items.into_iter().map(Item::from).collect(),
+                health_points: self.rewards.health_points,
+                attack_power: self.rewards.attack_power,
+                xp: self.rewards.xp,
+                flags: self.rewards.flags,
+            },
+            status,
+        })
+    }
+}
+
+impl World {
+    // Builds a world from the contents of a world file. See the format description at the top of this file.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(source: &str) -> Result<World, LoadError> {
+        let file: WorldFile =
+            toml::from_str(source).map_err(|e| LoadError::at(source, e.span(), e.message()))?;
+
+        let mut world = World::new();
+
+        for room in file.rooms {
+            // ids are what everything else links with, so two rooms can't share one
+            if world.rooms.contains_key(room.id.get_ref()) {
+                return Err(LoadError::at(
+                    source,
+                    Some(room.id.span()),
+                    &format!("duplicate room id '{}'", room.id.get_ref()),
+                ));
+            }
+            let id = world.create_room(
+                room.id.get_ref().as_str(),
+                &room.name,
+                &room.description,
+                room.items.into_iter().map(Item::from).collect(),
+                room.exits.into_iter().map(|(dir, exit)| (dir, exit.into())).collect(),
+            );
+            let created = world.room_mut(&id);
+            created.position = room.position;
+            created.checkpoint = room.checkpoint;
+            created.scenery = room
+                .scenery
+                .into_iter()
+                .map(|s| Scenery { name: s.name, description: s.description })
+                .collect();
+        }
+
+        for npc in file.npcs {
+            let xp = npc.xp;
+            let description = npc.description;
+            let inventory: Vec<Item> = npc.inventory.into_iter().map(Item::from).collect();
+            let loot: Vec<Item> = npc.loot.into_iter().map(Item::from).collect();
+            let trades: Vec<Trade> = npc
+                .trades
+                .into_iter()
+                .map(|t| Trade { wants: t.wants, gives: t.gives, reply: t.reply })
+                .collect();
+            if world.npc(npc.id.get_ref()).is_some() {
+                return Err(LoadError::at(
+                    source,
+                    Some(npc.id.span()),
+                    &format!("duplicate npc id '{}'", npc.id.get_ref()),
+                ));
+            }
+            let all_dialogues: HashMap<RoomId, Vec<String>> =
+                npc.dialogues.into_iter().map(|d| (d.room, d.lines)).collect();
+            let current_dialogue = all_dialogues.get(&npc.room).cloned().unwrap_or_default();
+            let mut conversations = HashMap::new();
+            for conversation in npc.conversations {
+                conversations.insert(conversation.room.clone(), conversation.into_tree(source)?);
+            }
+            let id = world.create_npc(
+                npc.id.get_ref().as_str(),
+                &npc.name,
+                current_dialogue,
+                npc.room,
+                all_dialogues,
+                npc.health_points,
+                npc.attack_power,
+                npc.hostile,
+            );
+            if let Some(npc) = world.npc_mut(&id) {
+                npc.description = description;
+                npc.conversations = conversations;
+                // tougher npcs are worth more unless the world says otherwise
+                npc.xp_reward = xp.unwrap_or((npc.health_points + npc.attack_power).max(0) as u32);
+                npc.inventory = inventory;
+                npc.loot = loot;
+                npc.trades = trades;
+            }
+        }
+
+        for quest in file.quests {
+            world.quests.push(quest.into_quest(source)?);
+        }
+
+        world.triggers = file.triggers.into_iter().map(Trigger::from).collect();
+
+        world.skills = file
+            .skills
+            .into_iter()
+            .map(|def| Skill {
+                name: def.name,
+                description: def.description,
+                level: def.level,
+                cooldown: def.cooldown,
+                effect: def.effect,
+            })
+            .collect();
+
+        // the player has to start somewhere that actually exists
+        if !world.rooms.contains_key(file.start_room.get_ref()) {
+            return Err(LoadError::at(
+                source,
+                Some(file.start_room.span()),
+                &format!("start_room '{}' is not the id of any room", file.start_room.get_ref()),
+            ));
+        }
+        world.start_room = file.start_room.into_inner();
+        world.infer_positions();
+
+        let defaults = DeathRules::default();
+        world.death = DeathRules {
+            policy: file.death.policy,
+            respawn_hp: file.death.respawn_hp.unwrap_or(defaults.respawn_hp),
+            xp_penalty: file.death.xp_penalty.unwrap_or(defaults.xp_penalty),
+            drop_items: file.death.drop_items.unwrap_or(defaults.drop_items),
+        };
+
+        Ok(world)
+    }
+
+    // Reads and builds a world from a file on disk
+    pub fn from_file(path: impl AsRef<Path>) -> Result<World, LoadError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path).map_err(|e| LoadError::Io {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })?;
+        World::from_str(&source)
+    }
+}
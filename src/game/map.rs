@@ -0,0 +1,151 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use crate::game::{
+    player::Player,
+    world::{RoomId, World},
+};
+
+/* MAP
+ * Rooms sit on a grid so the player can get a map of where they've been. A world can give a room
+ * its spot with `position`, everything else gets placed by walking the compass exits out from the
+ * rooms that are already placed. Rooms that only connect through other exits (stairs, portals...)
+ * or whose spot is already taken stay off the grid and get listed under the map instead.
+ */
+
+// how far one step through a compass exit moves you on the grid, y grows south
+fn offset(direction: &str) -> Option<(i32, i32)> {
+    match direction {
+        "north" => Some((0, -1)),
+        "south" => Some((0, 1)),
+        "east" => Some((1, 0)),
+        "west" => Some((-1, 0)),
+        _ => None,
+    }
+}
+
+impl World {
+    // Fills in the position of every room that can be reached through compass exits from a room
+    // that has one. The start room goes at 0,0 if the world didn't place it.
+    pub fn infer_positions(&mut self) {
+        if let Some(start) = self.rooms.get_mut(&self.start_room) {
+            start.position.get_or_insert((0, 0));
+        }
+
+        let mut taken: HashMap<(i32, i32), RoomId> = HashMap::new();
+        let mut placed: Vec<RoomId> = Vec::new();
+        for (id, room) in &self.rooms {
+            if let Some(cell) = room.position {
+                taken.entry(cell).or_insert_with(|| id.clone());
+                placed.push(id.clone());
+            }
+        }
+        // start room first, then the rest in id order so the same world always comes out the same
+        placed.sort_by_key(|id| (id != &self.start_room, id.clone()));
+
+        let mut queue: VecDeque<RoomId> = placed.into();
+        while let Some(id) = queue.pop_front() {
+            let room = &self.rooms[&id];
+            let Some((x, y)) = room.position else { continue };
+
+            let mut exits: Vec<_> = room.exits.iter().collect();
+            exits.sort_by(|a, b| a.0.cmp(b.0));
+            let steps: Vec<((i32, i32), RoomId)> = exits
+                .into_iter()
+                .filter_map(|(dir, exit)| offset(dir).map(|(dx, dy)| ((x + dx, y + dy), exit.to.clone())))
+                .collect();
+
+            for (cell, to) in steps {
+                let Some(next) = self.rooms.get_mut(&to) else { continue };
+                if next.position.is_some() || taken.contains_key(&cell) {
+                    continue;
+                }
+                next.position = Some(cell);
+                taken.insert(cell, to.clone());
+                queue.push_back(to);
+            }
+        }
+    }
+}
+
+// Draws the rooms the player has been to, plus the ones they've seen an exit into, on a grid.
+pub fn render(world: &World, player: &Player) -> String {
+    // what goes in each cell, true for visited
+    let mut cells: BTreeMap<(i32, i32), (RoomId, bool)> = BTreeMap::new();
+    for id in &player.visited {
+        if let Some(cell) = world.rooms.get(id).and_then(|r| r.position) {
+            cells.insert(cell, (id.clone(), true));
+        }
+    }
+    for id in &player.visited {
+        for dir in world.visible_exits(id) {
+            let Some(exit) = world.visible_exit(id, dir) else { continue };
+            if let Some(cell) = world.rooms.get(&exit.to).and_then(|r| r.position) {
+                cells.entry(cell).or_insert((exit.to.clone(), false));
+            }
+        }
+    }
+
+    let mut lines = Vec::new();
+    if !cells.is_empty() {
+        let min_x = cells.keys().map(|c| c.0).min().unwrap_or(0);
+        let max_x = cells.keys().map(|c| c.0).max().unwrap_or(0);
+        let min_y = cells.keys().map(|c| c.1).min().unwrap_or(0);
+        let max_y = cells.keys().map(|c| c.1).max().unwrap_or(0);
+        let width = ((max_x - min_x + 1) * 4) as usize;
+        let height = ((max_y - min_y + 1) * 2 - 1) as usize;
+        let mut grid = vec![vec![' '; width]; height];
+
+        for (&(x, y), (id, visited)) in &cells {
+            let col = ((x - min_x) * 4) as usize;
+            let row = ((y - min_y) * 2) as usize;
+            let mark = if *id == player.current_room {
+                '@'
+            } else if *visited {
+                ' '
+            } else {
+                '?'
+            };
+            grid[row][col] = '[';
+            grid[row][col + 1] = mark;
+            grid[row][col + 2] = ']';
+
+            // only draw the passages the player knows about, meaning from a room they've been in
+            if !*visited {
+                continue;
+            }
+            for dir in world.visible_exits(id) {
+                let Some((dx, dy)) = offset(dir) else { continue };
+                let Some(exit) = world.visible_exit(id, dir) else { continue };
+                if cells.get(&(x + dx, y + dy)).map(|c| &c.0) != Some(&exit.to) {
+                    continue;
+                }
+                match (dx, dy) {
+                    (1, 0) => grid[row][col + 3] = '-',
+                    (-1, 0) => grid[row][col - 1] = '-',
+                    (0, 1) => grid[row + 1][col + 1] = '|',
+                    _ => grid[row - 1][col + 1] = '|',
+                }
+            }
+        }
+
+        for row in grid {
+            lines.push(row.into_iter().collect::<String>().trim_end().to_string());
+        }
+        lines.push(String::new());
+    }
+
+    let here = world.rooms.get(&player.current_room).map(|r| r.name.as_str()).unwrap_or("?");
+    lines.push(format!("[@] you ({})  [ ] visited  [?] not explored yet", here));
+
+    let elsewhere: Vec<&str> = player
+        .visited
+        .iter()
+        .filter_map(|id| world.rooms.get(id))
+        .filter(|r| r.position.is_none())
+        .map(|r| r.name.as_str())
+        .collect();
+    if !elsewhere.is_empty() {
+        lines.push(format!("Elsewhere: {}", elsewhere.join(", ")));
+    }
+    lines.join("\n")
+}
@@ -1,4 +1,23 @@
 pub mod commands;
 pub mod player;
+pub mod quests;
+pub mod registry;
+pub mod rng;
 pub mod world;
 pub mod combat;
+pub mod containers;
+pub mod death;
+pub mod dialogue;
+pub mod examine;
+pub mod io;
+pub mod loader;
+pub mod map;
+pub mod parser;
+pub mod save;
+pub mod session;
+pub mod skills;
+#[cfg(test)]
+pub mod testing;
+pub mod travel;
+pub mod triggers;
+pub mod validate;
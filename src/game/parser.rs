@@ -0,0 +1,318 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::registry::CommandRegistry;
+
+/* PARSER
+ * Turns a line like "unlock the door with the rusty key" into the command it's for plus the
+ * phrases it's about: verb "unlock", object "door", preposition "with", target "rusty key".
+ * Verbs can be more than one word ("pick up") since they're just names and aliases in the registry.
+ * A bare direction ("north", "n") means go that way.
+ *
+ * Names are matched the forgiving way, see name_match: case doesn't matter, underscores in world
+ * files count as spaces, and "key" or "rusty key" both find an item called Rusty_Key. Adjectives can
+ * also come from the description, so "rusty key" finds a Key described as "A small rusty key."
+ * When a name fits more than one thing the command asks which one was meant (see
+ * commands::pick), and names that fit nothing get checked for typos with suggest.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Input {
+    pub verb: String,                // name of the command it matched, or the first word if nothing did
+    pub rest: String,                // everything after the verb as it was typed, for things that aren't names (save slots)
+    pub object: String,              // what the command is done to, lowercase with articles dropped
+    pub preposition: Option<String>, // "with", "to"...
+    pub target: String,              // whatever comes after the preposition, empty if there wasn't one
+}
+
+const ARTICLES: &[&str] = &["the", "a", "an", "some", "my"];
+const PREPOSITIONS: &[&str] = &["with", "to", "at", "on", "in", "into", "from", "using"];
+
+impl Input {
+    pub fn parse(line: &str, commands: &CommandRegistry) -> Input {
+        let typed: Vec<&str> = line.split_whitespace().collect();
+        let words: Vec<String> = typed.iter().map(|w| w.to_lowercase()).collect();
+        if words.is_empty() {
+            return Input::default();
+        }
+
+        // longest verb first so "pick up" wins over "pick"
+        let verb = (1..=words.len().min(2))
+            .rev()
+            .find_map(|n| commands.find(&words[..n].join(" ")).map(|c| (c.name.clone(), n)));
+        let (verb, used) = match verb {
+            Some(found) => found,
+            None if words.len() == 1 && direction(&words[0]).is_some() => {
+                return Input {
+                    verb: "go".to_string(),
+                    rest: typed[0].to_string(),
+                    object: words[0].clone(),
+                    ..Input::default()
+                };
+            }
+            None => (words[0].clone(), 1),
+        };
+
+        let mut phrase: Vec<&str> = words[used..]
+            .iter()
+            .map(|w| w.as_str())
+            .filter(|w| !ARTICLES.contains(w))
+            .collect();
+        // "talk to merlin", "look at the key": a preposition right after the verb is just how it's said
+        if phrase.first().is_some_and(|w| PREPOSITIONS.contains(w)) {
+            phrase.remove(0);
+        }
+        let (object, preposition, target) = match phrase.iter().position(|w| PREPOSITIONS.contains(w)) {
+            Some(i) => (&phrase[..i], Some(phrase[i].to_string()), &phrase[i + 1..]),
+            None => (&phrase[..], None, &[][..]),
+        };
+
+        Input {
+            verb,
+            rest: typed[used..].join(" "),
+            object: object.join(" "),
+            preposition,
+            target: target.join(" "),
+        }
+    }
+}
+
+// every direction a bare word can move in, see direction
+pub const DIRECTIONS: &[&str] = &["north", "south", "east", "west", "up", "down"];
+
+// the full direction for a compass word or its abbreviation
+pub fn direction(word: &str) -> Option<&'static str> {
+    match word {
+        "n" | "north" => Some("north"),
+        "s" | "south" => Some("south"),
+        "e" | "east" => Some("east"),
+        "w" | "west" => Some("west"),
+        "u" | "up" => Some("up"),
+        "d" | "down" => Some("down"),
+        _ => None,
+    }
+}
+
+// how a name is compared: lowercase, underscores as spaces
+pub fn normalize(name: &str) -> String {
+    name.to_lowercase().replace('_', " ")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NameMatch {
+    None,
+    Described, // the last word is in the name and the other words are in the description
+    Partial,   // every word is in the name
+    Exact,
+}
+
+// How well a phrase the player typed fits something with this name and description
+pub fn name_match(name: &str, description: &str, phrase: &str) -> NameMatch {
+    let name = normalize(name);
+    let phrase = normalize(phrase);
+    let name_words: Vec<&str> = name.split_whitespace().collect();
+    let phrase_words: Vec<&str> = phrase.split_whitespace().collect();
+
+    if phrase_words.is_empty() {
+        return NameMatch::None;
+    }
+    if name_words == phrase_words {
+        return NameMatch::Exact;
+    }
+    if phrase_words.iter().all(|w| name_words.contains(w)) {
+        return NameMatch::Partial;
+    }
+
+    let description = normalize(description);
+    let described: Vec<&str> = description
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    let (noun, adjectives) = phrase_words.split_last().expect("phrase isn't empty");
+    if name_words.contains(noun) && adjectives.iter().all(|w| name_words.contains(w) || described.contains(w)) {
+        return NameMatch::Described;
+    }
+    NameMatch::None
+}
+
+// What a name the player typed turned out to mean
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lookup<'a, T> {
+    Found(&'a T),
+    Ambiguous(Vec<&'a T>), // fits more than one thing equally well, the player has to pick
+    NotFound,
+}
+
+// Finds the thing that best fits a phrase. Things with the same name count as one (two identical
+// keys are the same key as far as the player is concerned), so it's only ambiguous between names
+pub fn lookup<'a, T>(
+    things: impl IntoIterator<Item = &'a T>,
+    phrase: &str,
+    name_and_description: impl Fn(&T) -> (&str, &str),
+) -> Lookup<'a, T> {
+    let mut best = NameMatch::None;
+    let mut found: Vec<&T> = Vec::new();
+    for thing in things {
+        let (name, description) = name_and_description(thing);
+        let fit = name_match(name, description, phrase);
+        if fit == NameMatch::None || fit < best {
+            continue;
+        }
+        if fit > best {
+            best = fit;
+            found.clear();
+        }
+        if !found.iter().any(|f| name_and_description(f).0 == name) {
+            found.push(thing);
+        }
+    }
+    match found.len() {
+        0 => Lookup::NotFound,
+        1 => Lookup::Found(found[0]),
+        _ => Lookup::Ambiguous(found),
+    }
+}
+
+// number of single letter edits to get from one word to the other. swapping two letters next to
+// each other counts as one, that's the most common typo there is
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    // d[i][j] is the distance between the first i letters of a and the first j of b
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    d[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+    d[a.len()][b.len()]
+}
+
+// The closest of some names to what the player typed, if it's close enough to be a typo.
+// Short words only get one typo, anything longer gets two
+pub fn suggest<'a>(typed: &str, names: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let typed = normalize(typed);
+    // one or two letters is too little to guess from
+    let allowed = match typed.chars().count() {
+        0..=2 => return None,
+        3..=4 => 1,
+        _ => 2,
+    };
+    names
+        .into_iter()
+        .map(|name| {
+            // close to the whole name or to any one word of it, "swrod" is close enough to Broken Sword.
+            // the whole name breaks ties so "ky" is the Key before it's the Brass Key
+            let normal = normalize(name);
+            let whole = edit_distance(&typed, &normal);
+            let closest = normal.split_whitespace().map(|word| edit_distance(&typed, word)).min().unwrap_or(whole);
+            (closest.min(whole), whole, name)
+        })
+        .filter(|(distance, _, _)| *distance > 0 && *distance <= allowed)
+        .min_by_key(|(distance, whole, _)| (*distance, *whole))
+        .map(|(_, _, name)| name)
+}
+
+// drops the/a/an from an answer to a question
+pub fn strip_articles(text: &str) -> String {
+    text.split_whitespace()
+        .filter(|w| !ARTICLES.contains(&w.to_lowercase().as_str()))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::commands;
+
+    fn parse(line: &str) -> Input {
+        Input::parse(line, &commands::builtins())
+    }
+
+    #[test]
+    fn articles_and_multi_word_verbs() {
+        let input = parse("pick up the rusty key");
+        assert_eq!(input.verb, "take");
+        assert_eq!(input.object, "rusty key");
+        assert_eq!(input.preposition, None);
+        assert_eq!(input.rest, "the rusty key");
+    }
+
+    #[test]
+    fn prepositions_split_object_and_target() {
+        let input = parse("give grass to merlin");
+        assert_eq!((input.verb.as_str(), input.object.as_str()), ("give", "grass"));
+        assert_eq!((input.preposition.as_deref(), input.target.as_str()), (Some("to"), "merlin"));
+
+        let input = parse("Unlock the door with my Key");
+        assert_eq!((input.verb.as_str(), input.object.as_str()), ("unlock", "door"));
+        assert_eq!((input.preposition.as_deref(), input.target.as_str()), (Some("with"), "key"));
+    }
+
+    #[test]
+    fn preposition_right_after_the_verb_is_dropped() {
+        let input = parse("talk to the merlin");
+        assert_eq!((input.verb.as_str(), input.object.as_str()), ("talk", "merlin"));
+        assert_eq!(input.preposition, None);
+    }
+
+    #[test]
+    fn bare_direction_is_go() {
+        let input = parse("n");
+        assert_eq!((input.verb.as_str(), input.object.as_str()), ("go", "n"));
+    }
+
+    #[test]
+    fn name_match_ranking() {
+        let description = "A small rusty key.";
+        assert_eq!(name_match("Broken Sword", "", "broken sword"), NameMatch::Exact);
+        assert_eq!(name_match("Rusty_Key", "", "rusty key"), NameMatch::Exact);
+        assert_eq!(name_match("Broken Sword", "", "sword"), NameMatch::Partial);
+        assert_eq!(name_match("Key", description, "rusty key"), NameMatch::Described);
+        assert_eq!(name_match("Key", description, "shiny key"), NameMatch::None);
+        assert_eq!(name_match("Key", description, ""), NameMatch::None);
+        assert!(NameMatch::Exact > NameMatch::Partial && NameMatch::Partial > NameMatch::Described);
+    }
+
+    fn by_name<'a>(thing: &'a (&str, &str)) -> (&'a str, &'a str) {
+        (thing.0, thing.1)
+    }
+
+    #[test]
+    fn lookup_asks_between_two_keys() {
+        let keys = [("Brass Key", "Shiny."), ("Rusty Key", "Old and rusty.")];
+        assert_eq!(lookup(&keys, "key", by_name), Lookup::Ambiguous(vec![&keys[0], &keys[1]]));
+        assert_eq!(lookup(&keys, "rusty key", by_name), Lookup::Found(&keys[1]));
+        assert_eq!(lookup(&keys, "sword", by_name), Lookup::NotFound);
+
+        // the same name twice is the same thing as far as the player can tell
+        let twins = [("Key", ""), ("Key", "")];
+        assert_eq!(lookup(&twins, "key", by_name), Lookup::Found(&twins[0]));
+    }
+
+    #[test]
+    fn swapped_letters_are_one_edit() {
+        assert_eq!(edit_distance("tkae", "take"), 1);
+        assert_eq!(edit_distance("norht", "north"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn suggestions() {
+        assert_eq!(suggest("tkae", ["take", "talk"]), Some("take"));
+        assert_eq!(suggest("swrod", ["Broken Sword"]), Some("Broken Sword"));
+        // exact matches aren't typos, and a couple of letters is too little to guess from
+        assert_eq!(suggest("take", ["take"]), None);
+        assert_eq!(suggest("stairs", ["go stairs"]), None);
+        assert_eq!(suggest("tk", ["take"]), None);
+        assert_eq!(suggest("xyzzy", ["take", "talk"]), None);
+    }
+}
@@ -1,59 +1,177 @@
+use crate::game::containers;
+use crate::game::skills::Skill;
 use crate::game::world::Item;
-use crate::game::world::World;
+use crate::game::world::{RoomId, Slot, World};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Player {
-    pub current_room: usize,
+    pub current_room: RoomId,
     pub inventory: Vec<Item>,// this may need to be converted to a hashmap
     pub health_points: i32,
-    pub attack_power: i32,
-    pub equipment: Vec<Item>,
+    pub attack_power: i32, // base attack and defense, without gear. use attack() and defense() for the real numbers
+    pub defense: i32,
+    pub equipment: BTreeMap<Slot, Item>, // one item per slot, equipped items aren't in the inventory
+    pub level: u32,
+    pub xp: u32,             // xp earned towards the next level, resets on level up
+    pub skills: Vec<String>, // names of the skills the player knows, see skills.rs
+    pub visited: BTreeSet<RoomId>, // rooms the player has been in, for the map
+    pub identified: BTreeSet<String>, // names of items the player has eaten, so they know what they do
+    pub checkpoint: Option<RoomId>,   // last checkpoint room entered, where they respawn. None = the start room
 }
 
+// xp needed to get from a level to the next one is the level times this
+pub const XP_PER_LEVEL: u32 = 100;
+// what every level up adds
+pub const LEVEL_UP_HP: i32 = 10;
+pub const LEVEL_UP_AP: i32 = 2;
+
 impl Player {
     //player constructor (?)
-    pub fn new(starting_room: usize) -> Self {
+    pub fn new(starting_room: RoomId) -> Self {
         Player {
+            visited: BTreeSet::from([starting_room.clone()]),
             current_room: starting_room,
             inventory: Vec::new(),
             health_points: 100,
             attack_power: 10,
-            equipment: Vec::new()
+            defense: 0,
+            equipment: BTreeMap::new(),
+            level: 1,
+            xp: 0,
+            skills: Vec::new(),
+            identified: BTreeSet::new(),
+            checkpoint: None,
+        }
+    }
+
+    // attack and defense are worked out from the base stats and whatever is equipped every time,
+    // so equipping and unequipping can never leave them out of sync
+    pub fn attack(&self) -> i32 {
+        self.attack_power + self.equipment.values().map(|i| i.attack_increase_amount).sum::<i32>()
+    }
+
+    pub fn defense(&self) -> i32 {
+        self.defense + self.equipment.values().map(|i| i.defense_increase_amount).sum::<i32>()
+    }
+
+    pub fn xp_to_next_level(&self) -> u32 {
+        self.level * XP_PER_LEVEL
+    }
+
+    // Adds xp, levelling up as many times as it covers. Returns the messages for everything that happened
+    pub fn gain_xp(&mut self, amount: u32, skills: &[Skill]) -> Vec<String> {
+        let mut lines = vec![format!("You gained {} XP.", amount)];
+        self.xp += amount;
+
+        while self.xp >= self.xp_to_next_level() {
+            self.xp -= self.xp_to_next_level();
+            self.level += 1;
+            self.health_points += LEVEL_UP_HP;
+            self.attack_power += LEVEL_UP_AP;
+            lines.push(format!(
+                "*** Level up! You are now level {}. HP {:+} AP {:+} ***",
+                self.level, LEVEL_UP_HP, LEVEL_UP_AP
+            ));
+            lines.extend(self.learn_skills(skills));
         }
+        lines
     }
 
-    pub fn equip_item(&mut self, item: Item) {
-        self.equipment.push(item);
+    // learns every skill the player's level is high enough for and doesn't know yet
+    pub fn learn_skills(&mut self, skills: &[Skill]) -> Vec<String> {
+        let mut lines = Vec::new();
+        for skill in skills.iter().filter(|s| s.level <= self.level) {
+            if !self.skills.contains(&skill.name) {
+                self.skills.push(skill.name.clone());
+                lines.push(format!("You learned {}: {}", skill.name, skill.description));
+            }
+        }
+        lines
+    }
+
+    // Moves an item from the inventory into its slot. Whatever was in the slot goes back to the
+    // inventory and is returned so the caller can mention it. Err if it can't be equipped
+    pub fn equip_item(&mut self, item_name: &str) -> Result<Option<Item>, String> {
+        let Some(slot) = self.inventory.iter().find(|i| i.name == item_name).map(|i| i.slot) else {
+            return Err("You don't have that.".to_string());
+        };
+        let Some(slot) = slot else {
+            return Err("You can't equip that.".to_string());
+        };
+
+        let item = self.remove_item(item_name).expect("item was just found in the inventory");
+        let replaced = self.equipment.insert(slot, item);
+        if let Some(old) = &replaced {
+            self.inventory.push(old.clone());
+        }
+        Ok(replaced)
     }
     //takes an item and adds it to player inventory vec
     pub fn take_item(&mut self, item: Item) {
         self.inventory.push(item);
     }
 
-    pub fn unequip_item(&mut self, item_name: &str) -> Option<Item> {
-        if let Some(index) = self.equipment.iter().position(|i| i.name == item_name) {
-            Some(self.equipment.remove(index))
-        } else {
-            None
-        }
+    // takes an item off and puts it back in the inventory
+    pub fn unequip_item(&mut self, item_name: &str) -> Option<Slot> {
+        let slot = *self.equipment.iter().find(|(_, i)| i.name == item_name)?.0;
+        let item = self.equipment.remove(&slot)?;
+        self.inventory.push(item);
+        Some(slot)
     }
 
+    // carrying it (a bag in the inventory counts) or wearing it
+    pub fn has_item(&self, item_name: &str) -> bool {
+        containers::contains(&self.inventory, item_name) || self.equipment.values().any(|i| i.name == item_name)
+    }
+
+    // Eats an item from the inventory and returns how much it healed (negative hurts). Once something
+    // has been eaten the player knows what that kind of item does
+    pub fn eat_item(&mut self, item_name: &str) -> Option<i32> {
+        let item = self.remove_item(item_name)?;
+        self.health_points += item.heal_amount;
+        self.identified.insert(item.name);
+        Some(item.heal_amount)
+    }
+
+    // takes an item out of the inventory, digging through any bags for it if it isn't loose
     pub fn remove_item(&mut self, item_name: &str) -> Option<Item> {
-        if let Some(index) = self.inventory.iter().position(|i| i.name == item_name) {
-            Some(self.inventory.remove(index))
-        } else {
-            None
+        containers::remove(&mut self.inventory, item_name)
+    }
+
+    // Move the player to an adjacent room if the direction/string key exists and nothing is in the way
+    pub fn move_room(&mut self, direction: &str, world: &World) -> Result<(), MoveError> {
+        let exit = world.passable_exit(&self.current_room, direction)?;
+        self.current_room = exit.to.clone();
+        self.visited.insert(exit.to.clone());
+        if world.rooms[&exit.to].checkpoint {
+            self.checkpoint = Some(exit.to.clone());
         }
+        Ok(())
     }
+}
+
+// why the player couldn't go somewhere
+#[derive(Debug, Clone, PartialEq)]
+pub enum MoveError {
+    NoExit,
+    Locked,
+    Closed,
+    Blocked(Option<String>), // gated by a flag, with the world's message for it if it has one
+}
 
-    // Move the player to an adjacent room if the direction/string key exists
-    pub fn move_room(&mut self, direction: &str, world: &World) -> Result<(), String> {
-        let current_room = &world.rooms[self.current_room];
-        if let Some(&next_room_index) = current_room.exits.get(direction) {
-            self.current_room = next_room_index;
-            Ok(())
-        } else {
-            Err("You can't go that way.".to_string())
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::NoExit => write!(f, "You can't go that way."),
+            MoveError::Locked => write!(f, "The door is locked."),
+            MoveError::Closed => write!(f, "The door is closed."),
+            MoveError::Blocked(Some(message)) => write!(f, "{}", message),
+            MoveError::Blocked(None) => write!(f, "Something blocks the way."),
         }
     }
 }
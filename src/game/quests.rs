@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    player::Player,
+    session::GameEvent,
+    world::{Item, NpcId, RoomId, World},
+};
+
+/* QUESTS
+ * A quest is an ordered list of objectives. Only the current objective is checked, and it's checked
+ * after every command, so progress happens on its own as the player plays. Quests either start
+ * active or wait for a flag (set by a conversation for example), and hand out rewards when done.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Quest {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub start_flag: Option<String>, // quest becomes active once this flag is set, None = active from the start
+    pub objectives: Vec<Objective>,
+    pub rewards: Rewards,
+    pub status: QuestStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Objective {
+    pub description: String, // what the journal shows
+    pub goal: Goal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Goal {
+    ReachRoom(RoomId),
+    ObtainItem(String),
+    DefeatNpc(NpcId),
+    TalkTo(NpcId),
+    SetFlag(String),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Rewards {
+    pub items: Vec<Item>,
+    pub health_points: i32,
+    pub attack_power: i32,
+    pub xp: u32,
+    pub flags: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QuestStatus {
+    Inactive,
+    Active { step: usize }, // index of the objective being worked on
+    Completed,
+}
+
+impl Goal {
+    // flags, rooms, items and defeated npcs are checked against the current state (so an npc
+    // beaten before the objective came up still counts), talking only counts if it happened during this command
+    fn is_met(&self, player: &Player, world: &World, events: &[GameEvent]) -> bool {
+        match self {
+            Goal::ReachRoom(room) => &player.current_room == room,
+            Goal::ObtainItem(name) => player.has_item(name),
+            Goal::SetFlag(flag) => world.get_flag(flag),
+            Goal::DefeatNpc(id) => world.npc(id).is_some_and(|n| n.dead),
+            Goal::TalkTo(id) => events
+                .iter()
+                .any(|e| matches!(e, GameEvent::Talked(npc) if npc == id)),
+        }
+    }
+}
+
+impl Rewards {
+    fn give(&self, player: &mut Player, world: &mut World) -> Vec<String> {
+        let mut lines = Vec::new();
+        for item in &self.items {
+            lines.push(format!("You received the {}.", item.name));
+            player.take_item(item.clone());
+        }
+        if self.health_points != 0 {
+            player.health_points += self.health_points;
+            lines.push(format!("HP {:+}", self.health_points));
+        }
+        if self.attack_power != 0 {
+            player.attack_power += self.attack_power;
+            lines.push(format!("AP {:+}", self.attack_power));
+        }
+        if self.xp > 0 {
+            lines.extend(player.gain_xp(self.xp, &world.skills));
+        }
+        for flag in &self.flags {
+            world.set_flag(flag, true);
+        }
+        lines
+    }
+}
+
+// Moves every quest along as far as it can go and returns the messages for anything that changed
+pub fn update_quests(player: &mut Player, world: &mut World, events: &[GameEvent]) -> Vec<String> {
+    let mut messages = Vec::new();
+
+    for index in 0..world.quests.len() {
+        let quest = &world.quests[index];
+        if quest.status == QuestStatus::Inactive {
+            if !quest.start_flag.as_ref().is_none_or(|f| world.get_flag(f)) {
+                continue;
+            }
+            messages.push(format!("*** New quest: {} ***\n{}", quest.name, quest.description));
+            world.quests[index].status = QuestStatus::Active { step: 0 };
+        }
+
+        // a single command can knock out several objectives in a row (e.g. the item was already carried)
+        while let QuestStatus::Active { step } = world.quests[index].status {
+            let quest = &world.quests[index];
+            match quest.objectives.get(step) {
+                Some(objective) if objective.goal.is_met(player, world, events) => {
+                    messages.push(format!("Quest updated: {} - {}", quest.name, objective.description));
+                    world.quests[index].status = QuestStatus::Active { step: step + 1 };
+                }
+                Some(_) => break,
+                None => {
+                    let rewards = quest.rewards.clone();
+                    messages.push(format!("*** Quest complete: {} ***", quest.name));
+                    world.quests[index].status = QuestStatus::Completed;
+                    messages.extend(rewards.give(player, world));
+                }
+            }
+        }
+    }
+
+    messages
+}
+
+// the quests/journal listing
+pub fn journal(world: &World) -> String {
+    let mut active = Vec::new();
+    let mut completed = Vec::new();
+
+    for quest in &world.quests {
+        match quest.status {
+            QuestStatus::Inactive => {}
+            QuestStatus::Active { step } => {
+                let mut lines = vec![format!("  {} - {}", quest.name, quest.description)];
+                for (i, objective) in quest.objectives.iter().enumerate().take(step + 1) {
+                    let mark = if i < step { "x" } else { " " };
+                    lines.push(format!("    [{}] {}", mark, objective.description));
+                }
+                active.push(lines.join("\n"));
+            }
+            QuestStatus::Completed => completed.push(format!("  {}", quest.name)),
+        }
+    }
+
+    if active.is_empty() && completed.is_empty() {
+        return "You don't have any quests yet.".to_string();
+    }
+
+    let mut out = vec!["Active quests:".to_string()];
+    if active.is_empty() {
+        out.push("  none".to_string());
+    }
+    out.extend(active);
+    out.push("Completed quests:".to_string());
+    if completed.is_empty() {
+        out.push("  none".to_string());
+    }
+    out.extend(completed);
+    out.join("\n")
+}
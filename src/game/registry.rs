@@ -0,0 +1,125 @@
+use crate::game::{parser::Input, session::Session};
+
+/* COMMAND REGISTRY
+ * Every command the player can type is registered here with its verb, aliases and help text, so a
+ * game can add its own verbs, swap out a built-in one or take one away without touching the engine.
+ * The built-in commands are just the default registrations, see commands::builtins().
+ *
+ *   let mut commands = commands::builtins();
+ *   commands.register(Command::new("dance", "dance", "Show off your moves.", |_, _| "You dance.".to_string()));
+ *   commands.remove("eat");
+ *
+ * `help` is a built-in like any other, made from what's registered here (see commands::help_command),
+ * so registering a command called "help" replaces it and removing it takes it away.
+ *
+ * A handler gets the parsed line (see parser.rs, input.verb is always the command's name even when
+ * an alias was typed) and the session, and returns what to print. One made with
+ * Command::with_registry also gets the registry it's running from, for commands like help that
+ * need to know what else there is.
+ */
+pub type Handler = Box<dyn Fn(&Input, &mut Session, &CommandRegistry) -> String>;
+
+pub struct Command {
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub usage: String,           // how to type it, "take <item>"
+    pub help: String,            // what it does, one line
+    pub details: Option<String>, // anything else worth knowing, shown by `help <command>`
+    pub anytime: bool,           // works in every mode (in a fight, mid conversation), not just while exploring
+    handler: Handler,
+}
+
+impl Command {
+    pub fn new(
+        name: &str,
+        usage: &str,
+        help: &str,
+        handler: impl Fn(&Input, &mut Session) -> String + 'static,
+    ) -> Self {
+        Command::with_registry(name, usage, help, move |input, session, _| handler(input, session))
+    }
+
+    // a command whose handler also gets the registry, see the top of this file
+    pub fn with_registry(
+        name: &str,
+        usage: &str,
+        help: &str,
+        handler: impl Fn(&Input, &mut Session, &CommandRegistry) -> String + 'static,
+    ) -> Self {
+        Command {
+            name: name.to_string(),
+            aliases: Vec::new(),
+            usage: usage.to_string(),
+            help: help.to_string(),
+            details: None,
+            anytime: false,
+            handler: Box::new(handler),
+        }
+    }
+
+    // another verb that does the same thing, "move" for "go"
+    pub fn alias(mut self, alias: &str) -> Self {
+        self.aliases.push(alias.to_string());
+        self
+    }
+
+    pub fn details(mut self, details: &str) -> Self {
+        self.details = Some(details.to_string());
+        self
+    }
+
+    pub fn anytime(mut self) -> Self {
+        self.anytime = true;
+        self
+    }
+
+    pub fn run(&self, input: &Input, session: &mut Session, commands: &CommandRegistry) -> String {
+        (self.handler)(input, session, commands)
+    }
+
+    // does this verb run this command
+    pub fn answers_to(&self, verb: &str) -> bool {
+        self.name == verb || self.aliases.iter().any(|a| a == verb)
+    }
+}
+
+// The commands a game understands, in the order they were registered
+#[derive(Default)]
+pub struct CommandRegistry {
+    commands: Vec<Command>,
+}
+
+impl CommandRegistry {
+    pub fn new() -> Self {
+        CommandRegistry::default()
+    }
+
+    // Adds a command. One with the same name is replaced in place, and any other command that
+    // had one of its verbs as an alias loses it, so the newest registration always wins
+    pub fn register(&mut self, command: Command) {
+        for other in self.commands.iter_mut().filter(|c| c.name != command.name) {
+            other.aliases.retain(|a| !command.answers_to(a));
+        }
+        match self.commands.iter_mut().find(|c| c.name == command.name) {
+            Some(existing) => *existing = command,
+            None => self.commands.push(command),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Command> {
+        let index = self.commands.iter().position(|c| c.name == name)?;
+        Some(self.commands.remove(index))
+    }
+
+    // the command for a verb the player typed, by name first and then by alias
+    pub fn find(&self, verb: &str) -> Option<&Command> {
+        self.commands
+            .iter()
+            .find(|c| c.name == verb)
+            .or_else(|| self.commands.iter().find(|c| c.answers_to(verb)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Command> {
+        self.commands.iter()
+    }
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+/* RANDOM NUMBERS
+ * Small seedable generator (splitmix64) that lives in the session. The whole state is one number
+ * that gets saved with the game, so the same seed and the same commands always play out the same
+ * way, which makes fights reproducible for bug reports. Written out here instead of pulling in a
+ * crate so a seed means the same thing forever and it works the same in the browser.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rng {
+    pub seed: u64, // what the game was started with, for reporting
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng { seed, state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    // between 0.0 and 1.0 (never 1.0)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    // true with the given probability, 0.0 = never, 1.0 = always
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+
+    // somewhere between low and high
+    pub fn range(&mut self, low: f32, high: f32) -> f32 {
+        low + (high - low) * self.next_f32()
+    }
+}
@@ -0,0 +1,126 @@
+use std::{fmt, fs, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::game::session::Session;
+
+// Bump this whenever the Session changes shape in a way old saves can't be read into.
+pub const SAVE_VERSION: u32 = 17;
+
+// folder the save/load commands read and write slots in
+pub const SAVE_DIR: &str = "saves";
+
+// Everything needed to pick a game back up exactly where it was left: the whole player, the
+// whole world (room items, npc positions/hp/dialogue counters, flags) and any fight in progress.
+#[derive(Serialize, Deserialize)]
+struct SaveFile {
+    version: u32,
+    session: Session,
+}
+
+// only used to peek at the version before trying to read the rest of the file
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SaveError {
+    InvalidSlot(String),
+    NotFound(String),
+    Io(String),
+    Corrupt { line: usize, column: usize, message: String },
+    Incompatible { found: u32, expected: u32 },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::InvalidSlot(slot) => write!(
+                f,
+                "'{}' is not a valid save slot. Use letters, numbers, '-' or '_'.",
+                slot
+            ),
+            SaveError::NotFound(slot) => write!(f, "There is no save in slot '{}'.", slot),
+            SaveError::Io(message) => write!(f, "Could not access the save file: {}", message),
+            SaveError::Corrupt { line, column, message } => write!(
+                f,
+                "The save file is corrupt (line {}, column {}): {}",
+                line, column, message
+            ),
+            SaveError::Incompatible { found, expected } => write!(
+                f,
+                "The save file is from an incompatible version (save version {}, game expects {}).",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(err: serde_json::Error) -> Self {
+        // serde_json tacks "at line X column Y" onto its message, we already report those
+        let mut message = err.to_string();
+        if let Some(index) = message.rfind(" at line ") {
+            message.truncate(index);
+        }
+        SaveError::Corrupt {
+            line: err.line(),
+            column: err.column(),
+            message,
+        }
+    }
+}
+
+// Serializes the full game state into a versioned save string
+pub fn to_string(session: &Session) -> Result<String, SaveError> {
+    let save = SaveFile {
+        version: SAVE_VERSION,
+        session: session.clone(),
+    };
+    Ok(serde_json::to_string_pretty(&save)?)
+}
+
+// Reads a save string back into a session, checking the version first so an old save
+// gets a clear "incompatible" error instead of a confusing missing field error.
+pub fn from_str(data: &str) -> Result<Session, SaveError> {
+    let header: SaveHeader = serde_json::from_str(data)?;
+    if header.version != SAVE_VERSION {
+        return Err(SaveError::Incompatible {
+            found: header.version,
+            expected: SAVE_VERSION,
+        });
+    }
+    let save: SaveFile = serde_json::from_str(data)?;
+    Ok(save.session)
+}
+
+// slot names end up as file names, so keep them boring (no paths, dots, etc.)
+fn slot_path(slot: &str) -> Result<PathBuf, SaveError> {
+    let valid = !slot.is_empty()
+        && slot
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(SaveError::InvalidSlot(slot.to_string()));
+    }
+    Ok(PathBuf::from(SAVE_DIR).join(format!("{}.json", slot)))
+}
+
+pub fn save_slot(slot: &str, session: &Session) -> Result<(), SaveError> {
+    let path = slot_path(slot)?;
+    let data = to_string(session)?;
+    fs::create_dir_all(SAVE_DIR).map_err(|e| SaveError::Io(e.to_string()))?;
+    fs::write(path, data).map_err(|e| SaveError::Io(e.to_string()))
+}
+
+pub fn load_slot(slot: &str) -> Result<Session, SaveError> {
+    let path = slot_path(slot)?;
+    if !path.exists() {
+        return Err(SaveError::NotFound(slot.to_string()));
+    }
+    let data = fs::read_to_string(path).map_err(|e| SaveError::Io(e.to_string()))?;
+    from_str(&data)
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    combat::Combat,
+    dialogue::Conversation,
+    parser::Input,
+    player::Player,
+    rng::Rng,
+    world::{NpcId, World},
+};
+
+// What the next line of input means. Most of the time it's a regular command, but while
+// something is waiting on an answer (a fight, a conversation, a yes/no question) input goes there instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Mode {
+    Explore,
+    ConfirmAttack(NpcId), // waiting on y/n before attacking a non-hostile npc
+    Combat(Combat),
+    Dialogue(Conversation), // talking to an npc, input picks a numbered response
+    // a name in the command fit more than one thing, waiting on which one. the command runs again
+    // with the answer in place of the phrase, see commands::pick
+    Choosing { input: Input, phrase: String, options: Vec<String>, question: String },
+    GameOver, // the player died for good, see death.rs. nothing works anymore
+}
+
+// Things that happened during a command that don't leave anything behind in the world state,
+// so quests (and anything else that runs after a command) can still react to them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GameEvent {
+    Talked(NpcId),
+}
+
+// A whole running game: the player, the world they are in, and what mode input is in.
+// This is what gets saved and loaded, so a game can be picked back up in the middle of a fight.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub player: Player,
+    pub world: World,
+    pub mode: Mode,
+    pub turn: u32, // commands entered so far
+    pub rng: Rng,  // everything random comes from here so a seed replays the same game
+    #[serde(skip)]
+    pub events: Vec<GameEvent>, // cleared after every command
+    #[serde(skip)]
+    pub last_save: Option<String>, // the last save made or loaded, for reloading on death
+    #[serde(skip)]
+    pub fresh_world: Option<Box<World>>, // the world as it was loaded, for restarting on death
+}
+
+impl Session {
+    // puts a new player in the world's starting room. the same seed plays out the same way
+    pub fn new(world: World, seed: u64) -> Self {
+        let mut player = Player::new(world.start_room.clone());
+        player.learn_skills(&world.skills); // the level 1 skills
+        Session {
+            player,
+            fresh_world: Some(Box::new(world.clone())),
+            world,
+            mode: Mode::Explore,
+            turn: 0,
+            rng: Rng::new(seed),
+            events: Vec::new(),
+            last_save: None,
+        }
+    }
+
+    pub fn in_combat(&self) -> bool {
+        matches!(self.mode, Mode::Combat(_))
+    }
+
+    pub fn is_over(&self) -> bool {
+        matches!(self.mode, Mode::GameOver)
+    }
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+/* SKILLS
+ * Skills come from the world file like everything else. The player learns every skill whose level
+ * they've reached, starting with the level 1 ones, and picks them from the combat prompt with [S]kill.
+ * Each skill has one effect and a cooldown in rounds so the big ones can't be spammed.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Skill {
+    pub name: String,
+    pub description: String,
+    pub level: u32,    // player level it's learned at
+    pub cooldown: u32, // rounds it can't be used for after using it
+    pub effect: SkillEffect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkillEffect {
+    Damage(f32), // attack with the player's AP times this
+    Heal(i32),
+    Stun(u32), // the enemy skips this many turns
+}
+
+impl SkillEffect {
+    // short description for skill lists
+    pub fn summary(&self) -> String {
+        match self {
+            SkillEffect::Damage(multiplier) => format!("{}x damage", multiplier),
+            SkillEffect::Heal(amount) => format!("heals {}", amount),
+            SkillEffect::Stun(1) => "stuns for 1 turn".to_string(),
+            SkillEffect::Stun(turns) => format!("stuns for {} turns", turns),
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::game::{
+    commands::{self, process_command},
+    io::GameIo,
+    session::Session,
+    world::World,
+};
+
+/* TEST HELPERS
+ * What the engine's tests share: a world from a TOML string, a session in it, and commands run
+ * through process_command with the built-in registry the same way the terminal runs them.
+ */
+
+// a small world for tests that don't need anything special: a key in the first room, a room
+// to the north and one down some stairs (an exit that isn't a compass direction)
+pub const WORLD: &str = r#"
+    start_room = "a"
+
+    [[rooms]]
+    id = "a"
+    name = "Room A"
+    description = "Room A."
+    exits = { north = "b", stairs = "c" }
+
+    [[rooms.items]]
+    name = "Key"
+    description = "A small rusty key."
+
+    [[rooms]]
+    id = "b"
+    name = "Room B"
+    description = "Room B."
+    exits = { south = "a" }
+
+    [[rooms]]
+    id = "c"
+    name = "Cellar"
+    description = "The bottom of the stairs."
+    exits = { up = "a" }
+"#;
+
+pub fn new_session(world: &str, seed: u64) -> Session {
+    Session::new(World::from_str(world).expect("test world should load"), seed)
+}
+
+// runs each line as a command and hands back every response
+pub fn run(session: &mut Session, lines: &[&str]) -> Vec<String> {
+    let commands = commands::builtins();
+    lines.iter().map(|line| process_command(line, session, &commands)).collect()
+}
+
+// a new game in the world with the seed, and the responses to the lines
+pub fn run_script(world: &str, seed: u64, lines: &[&str]) -> Vec<String> {
+    run(&mut new_session(world, seed), lines)
+}
+
+// the terminal's main loop minus the terminal: prompt, run, write, until the input runs out
+pub fn play(world: &str, seed: u64, io: &mut impl GameIo) {
+    let mut session = new_session(world, seed);
+    let commands = commands::builtins();
+    while let Some(line) = io.prompt("> ") {
+        let response = process_command(&line, &mut session, &commands);
+        io.write_line(&response);
+    }
+}
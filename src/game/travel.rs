@@ -0,0 +1,53 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use crate::game::world::{RoomId, World};
+
+/* TRAVEL
+ * `travel <room>` walks the player back to somewhere they've already been. The route is the
+ * shortest one through rooms they've visited, using only exits they could walk through right now
+ * (no locked or closed doors, no exits still waiting on a flag). The walking itself is done one
+ * step at a time by the travel command so every room on the way counts like a normal move.
+ */
+impl World {
+    // A visited room by its name or id, ignoring case
+    pub fn find_visited_room(&self, name: &str, visited: &BTreeSet<RoomId>) -> Option<RoomId> {
+        visited
+            .iter()
+            .find(|id| {
+                id.as_str().eq_ignore_ascii_case(name)
+                    || self.rooms.get(*id).is_some_and(|r| r.name.eq_ignore_ascii_case(name))
+            })
+            .cloned()
+    }
+
+    // Directions to take to get from one room to another, shortest first. None if there's no way
+    // through the visited rooms. Directions are tried in sorted order so the route is always the same
+    pub fn find_path(&self, from: &RoomId, to: &RoomId, visited: &BTreeSet<RoomId>) -> Option<Vec<String>> {
+        // room -> (room we came from, direction we took)
+        let mut came_from: HashMap<RoomId, (RoomId, String)> = HashMap::new();
+        let mut queue = VecDeque::from([from.clone()]);
+
+        while let Some(room) = queue.pop_front() {
+            if &room == to {
+                let mut path = Vec::new();
+                let mut at = room;
+                while let Some((previous, direction)) = came_from.get(&at) {
+                    path.push(direction.clone());
+                    at = previous.clone();
+                }
+                path.reverse();
+                return Some(path);
+            }
+
+            for direction in self.visible_exits(&room) {
+                let Ok(exit) = self.passable_exit(&room, direction) else { continue };
+                if &exit.to == from || came_from.contains_key(&exit.to) || !visited.contains(&exit.to) {
+                    continue;
+                }
+                came_from.insert(exit.to.clone(), (room.clone(), direction.clone()));
+                queue.push_back(exit.to.clone());
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+
+use crate::game::{
+    player::Player,
+    world::{Exit, Item, NpcId, RoomId, World},
+};
+
+/* EVENT TRIGGERS
+ * The "flag checker" that runs after every command. A trigger is a list of conditions and a list of
+ * actions: once every condition holds, the actions run in order. Most triggers only fire once, but
+ * they can be made repeatable.
+ * This is where scripted moments live, like Merlin moving on after the skeleton is gone.
+ */
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trigger {
+    pub id: String,
+    pub conditions: Vec<Condition>,
+    pub actions: Vec<Action>,
+    pub once: bool,  // fire a single time, or every command the conditions hold
+    pub fired: bool, // has it fired yet
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Condition {
+    FlagSet(String),
+    FlagUnset(String),
+    PlayerInRoom(RoomId),
+    HasItem(String), // carried or equipped
+    NpcDead(NpcId),
+    TurnAtLeast(u32), // commands entered since the game started
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Action {
+    SetFlag(String),
+    ClearFlag(String),
+    MoveNpc { npc: NpcId, room: RoomId },
+    SpawnItem { room: RoomId, item: Item },
+    RemoveItem { room: RoomId, item: String },
+    OpenExit { room: RoomId, direction: String, to: RoomId },
+    RevealExit { room: RoomId, direction: String }, // makes a hidden exit visible
+    UnlockExit { room: RoomId, direction: String }, // unlocks and opens the door on an exit
+    Message(String),
+}
+
+impl Condition {
+    fn holds(&self, player: &Player, world: &World, turn: u32) -> bool {
+        match self {
+            Condition::FlagSet(flag) => world.get_flag(flag),
+            Condition::FlagUnset(flag) => !world.get_flag(flag),
+            Condition::PlayerInRoom(room) => &player.current_room == room,
+            Condition::HasItem(name) => player.has_item(name),
+            Condition::NpcDead(id) => world.npc(id).is_some_and(|n| n.dead),
+            Condition::TurnAtLeast(count) => turn >= *count,
+        }
+    }
+}
+
+impl Action {
+    // runs the action, returning a message for the player if there is one
+    fn apply(&self, world: &mut World) -> Option<String> {
+        match self {
+            Action::SetFlag(flag) => world.set_flag(flag, true),
+            Action::ClearFlag(flag) => world.set_flag(flag, false),
+            Action::MoveNpc { npc, room } => {
+                if let Some(npc) = world.npc_mut(npc) {
+                    npc.move_to_room(room);
+                }
+            }
+            Action::SpawnItem { room, item } => {
+                if let Some(room) = world.rooms.get_mut(room) {
+                    room.items.push(item.clone());
+                }
+            }
+            Action::RemoveItem { room, item } => {
+                if let Some(room) = world.rooms.get_mut(room) {
+                    room.items.retain(|i| &i.name != item);
+                }
+            }
+            Action::OpenExit { room, direction, to } => {
+                if let Some(room) = world.rooms.get_mut(room) {
+                    room.exits.insert(direction.clone(), Exit::to(to.clone()));
+                }
+            }
+            Action::RevealExit { room, direction } => {
+                if let Some(exit) = world.rooms.get_mut(room).and_then(|r| r.exits.get_mut(direction)) {
+                    exit.hidden = false;
+                }
+            }
+            Action::UnlockExit { room, direction } => world.update_door(room, direction, |door| {
+                door.locked = false;
+                door.closed = false;
+            }),
+            Action::Message(text) => return Some(text.clone()),
+        }
+        None
+    }
+}
+
+// Checks every trigger and runs the ones whose conditions hold. Returns any messages to show.
+pub fn run_triggers(player: &Player, world: &mut World, turn: u32) -> Vec<String> {
+    let mut messages = Vec::new();
+
+    for index in 0..world.triggers.len() {
+        let trigger = &world.triggers[index];
+        if trigger.once && trigger.fired {
+            continue;
+        }
+        if !trigger.conditions.iter().all(|c| c.holds(player, world, turn)) {
+            continue;
+        }
+
+        let actions = trigger.actions.clone();
+        world.triggers[index].fired = true;
+        messages.extend(actions.iter().filter_map(|action| action.apply(world)));
+    }
+
+    messages
+}
@@ -0,0 +1,180 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt,
+};
+
+use crate::game::{
+    triggers::Action,
+    world::{NpcId, RoomId, World},
+};
+
+/* WORLD VALIDATION
+ * The loader only checks that a world file is shaped right. This checks that the world makes sense
+ * as a map: exits go somewhere, every room can be reached, npcs stand in real rooms and so on.
+ * It runs once on startup so a broken world is reported up front instead of panicking mid game.
+ */
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Problem {
+    MissingStartRoom(RoomId),
+    DanglingExit { room: RoomId, direction: String, to: RoomId },
+    UnreachableRoom(RoomId),
+    NpcInMissingRoom { npc: NpcId, room: RoomId },
+    DialogueForMissingRoom { npc: NpcId, room: RoomId },
+    EmptyDialogue { npc: NpcId, room: RoomId },
+    DuplicateItem { room: RoomId, item: String },
+    DuplicateNpc { room: RoomId, name: String },
+    TradeItemMissing { npc: NpcId, item: String },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::MissingStartRoom(room) => {
+                write!(f, "the start room '{}' does not exist", room)
+            }
+            Problem::DanglingExit { room, direction, to } => write!(
+                f,
+                "room '{}': exit '{}' leads to '{}', which does not exist",
+                room, direction, to
+            ),
+            Problem::UnreachableRoom(room) => {
+                write!(f, "room '{}' can't be reached from the start room", room)
+            }
+            Problem::NpcInMissingRoom { npc, room } => {
+                write!(f, "npc '{}' is placed in '{}', which does not exist", npc, room)
+            }
+            Problem::DialogueForMissingRoom { npc, room } => write!(
+                f,
+                "npc '{}' has dialogue for '{}', which does not exist",
+                npc, room
+            ),
+            Problem::EmptyDialogue { npc, room } => {
+                write!(f, "npc '{}' has an empty dialogue list for room '{}'", npc, room)
+            }
+            Problem::DuplicateItem { room, item } => {
+                write!(f, "room '{}' has more than one item named '{}'", room, item)
+            }
+            Problem::DuplicateNpc { room, name } => {
+                write!(f, "room '{}' has more than one npc named '{}'", room, name)
+            }
+            Problem::TradeItemMissing { npc, item } => write!(
+                f,
+                "npc '{}' trades away '{}', which isn't in their inventory",
+                npc, item
+            ),
+        }
+    }
+}
+
+impl World {
+    // Checks the whole world and returns everything wrong with it, sorted so the list reads the
+    // same every time. An empty list means the world is good to play.
+    pub fn validate(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+
+        if !self.rooms.contains_key(&self.start_room) {
+            problems.push(Problem::MissingStartRoom(self.start_room.clone()));
+        }
+
+        for (id, room) in &self.rooms {
+            for (direction, exit) in &room.exits {
+                if !self.rooms.contains_key(&exit.to) {
+                    problems.push(Problem::DanglingExit {
+                        room: id.clone(),
+                        direction: direction.clone(),
+                        to: exit.to.clone(),
+                    });
+                }
+            }
+
+            let mut seen = HashSet::new();
+            for item in &room.items {
+                if !seen.insert(&item.name) {
+                    problems.push(Problem::DuplicateItem {
+                        room: id.clone(),
+                        item: item.name.clone(),
+                    });
+                }
+            }
+        }
+
+        let reachable = self.reachable_rooms();
+        for id in self.rooms.keys() {
+            if !reachable.contains(id) {
+                problems.push(Problem::UnreachableRoom(id.clone()));
+            }
+        }
+
+        let mut npc_names = HashSet::new();
+        for npc in &self.npcs {
+            if !self.rooms.contains_key(&npc.current_room) {
+                problems.push(Problem::NpcInMissingRoom {
+                    npc: npc.id.clone(),
+                    room: npc.current_room.clone(),
+                });
+            } else if !npc.dead && !npc_names.insert((&npc.current_room, &npc.name)) {
+                problems.push(Problem::DuplicateNpc {
+                    room: npc.current_room.clone(),
+                    name: npc.name.clone(),
+                });
+            }
+
+            let dialogue_rooms = npc.all_dialogues.keys().chain(npc.conversations.keys());
+            for room in dialogue_rooms.collect::<HashSet<_>>() {
+                if !self.rooms.contains_key(room) {
+                    problems.push(Problem::DialogueForMissingRoom {
+                        npc: npc.id.clone(),
+                        room: room.clone(),
+                    });
+                }
+            }
+            for item in npc.trades.iter().filter_map(|t| t.gives.as_ref()) {
+                if !npc.inventory.iter().any(|i| &i.name == item) {
+                    problems.push(Problem::TradeItemMissing {
+                        npc: npc.id.clone(),
+                        item: item.clone(),
+                    });
+                }
+            }
+            for (room, lines) in &npc.all_dialogues {
+                if lines.is_empty() {
+                    problems.push(Problem::EmptyDialogue {
+                        npc: npc.id.clone(),
+                        room: room.clone(),
+                    });
+                }
+            }
+        }
+
+        problems.sort();
+        problems
+    }
+
+    // every room the player can walk to from the start, counting exits that triggers open later.
+    // locked, closed and hidden exits count too, the player is meant to get through them eventually
+    fn reachable_rooms(&self) -> HashSet<&RoomId> {
+        let mut reachable = HashSet::new();
+        let mut queue = VecDeque::new();
+        if let Some((start, _)) = self.rooms.get_key_value(&self.start_room) {
+            reachable.insert(start);
+            queue.push_back(start);
+        }
+
+        while let Some(id) = queue.pop_front() {
+            let exits = self.rooms[id].exits.values().map(|exit| &exit.to);
+            let opened = self.triggers.iter().flat_map(|t| &t.actions).filter_map(|a| match a {
+                Action::OpenExit { room, to, .. } if room == id => Some(to),
+                _ => None,
+            });
+            for to in exits.chain(opened) {
+                if let Some((to, _)) = self.rooms.get_key_value(to) {
+                    if reachable.insert(to) {
+                        queue.push_back(to);
+                    }
+                }
+            }
+        }
+
+        reachable
+    }
+}
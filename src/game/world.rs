@@ -1,74 +1,256 @@
-use std::{collections::HashMap, hash::Hash};
+use std::{borrow::Borrow, collections::HashMap, fmt};
 
-use serde::de::value::UnitDeserializer;
+use serde::{Deserialize, Serialize};
 
+use crate::game::{
+    death::DeathRules, dialogue::DialogueTree, player::MoveError, quests::Quest, skills::Skill,
+    triggers::Trigger,
+};
+
+/* IDS
+ * Rooms and npcs are referred to by string ids that come straight from the world file
+ * ("maze_entrance", "merlin"), never by their position in a list. Ids don't change when
+ * things get added or an npc dies, so anything can hold on to one (exits, combat, quests, saves).
+ */
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct RoomId(pub String);
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct NpcId(pub String);
+
+macro_rules! impl_id {
+    ($id:ident) => {
+        impl $id {
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl From<&str> for $id {
+            fn from(id: &str) -> Self {
+                $id(id.to_string())
+            }
+        }
+
+        impl From<String> for $id {
+            fn from(id: String) -> Self {
+                $id(id)
+            }
+        }
+
+        // lets maps keyed by id be looked up with a plain &str
+        impl Borrow<str> for $id {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $id {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+    };
+}
+
+impl_id!(RoomId);
+impl_id!(NpcId);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct World {
-    pub rooms: Vec<Room>, // List of all rooms in the game world
-    pub npcs: Vec<Npc>,   // list of all npcs in the game world
-    pub flags: HashMap<String, bool>
+    pub rooms: HashMap<RoomId, Room>, // every room in the game world by id
+    pub npcs: Vec<Npc>,               // list of all npcs in the game world, dead ones included
+    pub flags: HashMap<String, bool>,
+    pub start_room: RoomId, // room a new player starts in
+    pub quests: Vec<Quest>, // every quest in the game along with its progress, see quests.rs
+    pub triggers: Vec<Trigger>, // scripted events checked after every command, see triggers.rs
+    pub skills: Vec<Skill>,     // every skill the player can learn, see skills.rs
+    pub death: DeathRules,      // what happens when the player dies, see death.rs
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Room {
     pub name: String,
     pub description: String,
-    pub items: Vec<Item>,              //items in room
-    pub exits: HashMap<String, usize>, // Direction/string to world vec index map
+    pub items: Vec<Item>,               //items in room
+    pub exits: HashMap<String, Exit>, // Direction/string to exit map
+    pub position: Option<(i32, i32)>, // spot on the map grid, x grows east and y grows south. see map.rs
+    pub scenery: Vec<Scenery>, // things in the room that can be examined but not taken
+    pub checkpoint: bool,      // entering it makes it where the player respawns, see death.rs
+}
+
+// Part of a room that's worth a closer look but isn't an item, like a statue or writing on a wall
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scenery {
+    pub name: String,
+    pub description: String,
+}
+
+// One way out of a room. Most exits are just a room id, but they can also have a door on them,
+// be hidden until something reveals them, or only let the player through once a flag is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Exit {
+    pub to: RoomId,
+    pub door: Option<Door>,
+    pub hidden: bool,                    // not listed or usable until a trigger reveals it
+    pub requires_flag: Option<String>,   // can't be used until this flag is set
+    pub blocked_message: Option<String>, // what the player is told when the flag isn't set
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Door {
+    pub closed: bool,
+    pub locked: bool,        // locked doors are always closed too
+    pub key: Option<String>, // item that unlocks it, None = only a trigger can unlock it
+}
+
+impl Exit {
+    // a plain exit with nothing in the way
+    pub fn to(room: RoomId) -> Exit {
+        Exit {
+            to: room,
+            door: None,
+            hidden: false,
+            requires_flag: None,
+            blocked_message: None,
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Item {
     pub name: String,
     pub description: String,
     pub can_take: bool, //some objects cannot/should not be picked up
     pub can_eat: bool,
-    pub can_equip: bool,
+    pub slot: Option<Slot>, // where it goes when equipped, None = can't be equipped
     pub heal_amount: i32,
-    pub attack_increase_amount: i32
-    
+    pub attack_increase_amount: i32,
+    pub defense_increase_amount: i32,
+    pub container: Option<Container>, // chests, bags... None = nothing goes inside it, see containers.rs
 }
 
-#[derive(Debug, Clone)]
+// What makes an item something other items go in. Works like a door: closed ones hide what's
+// inside, locked ones open with their key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Container {
+    pub items: Vec<Item>,
+    pub closed: bool,
+    pub locked: bool,        // locked containers are always closed too
+    pub key: Option<String>, // item that unlocks it, None = can't be unlocked
+}
+
+// Equipment slots. The player can wear one item in each
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Slot {
+    Weapon,
+    Offhand,
+    Head,
+    Body,
+    Accessory,
+}
+
+impl fmt::Display for Slot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Slot::Weapon => "weapon",
+            Slot::Offhand => "offhand",
+            Slot::Head => "head",
+            Slot::Body => "body",
+            Slot::Accessory => "accessory",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Npc {
+    pub id: NpcId,
     pub name: String,
+    pub description: String, // what `examine` shows, can be empty
     pub current_dialogue: Vec<String>, //current dialogue is a vec of string sentences
-    pub current_room: usize,
-    pub all_dialogues: HashMap<usize, Vec<String>>, //dialogue vector based on room index
+    pub current_room: RoomId,
+    pub all_dialogues: HashMap<RoomId, Vec<String>>, //dialogue vector based on room
     pub dialogue_counter: usize,                    // set this to zero
     pub health_points: i32, // basic hp
+    pub max_health_points: i32, // what they started with, for how hurt they look
     pub attack_power: i32, // basic attack
     pub hostile: bool, // hostility state, monsters can be considered npcs as well as hostile npcs like in dark souls
+    pub conversations: HashMap<RoomId, DialogueTree>, // branching conversations based on room, see dialogue.rs
+    pub dead: bool, // defeated npcs stay in the list so ids held elsewhere still work
+    pub xp_reward: u32, // xp the player gets for defeating them
+    pub inventory: Vec<Item>, // what they're carrying. it's dropped when they're defeated
+    pub loot: Vec<Item>,      // extra items only dropped on defeat
+    pub trades: Vec<Trade>,   // what they'll take through `give <item> to <npc>`
 }
 
+// An item a friendly npc will accept, and what they hand back for it if anything
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trade {
+    pub wants: String,         // name of the item they want
+    pub gives: Option<String>, // name of an item from their inventory they give in return
+    pub reply: Option<String>, // what they say when it happens
+}
 
 
 impl Npc {
     pub fn speak_dialogue(&mut self) -> String {
+        //npcs with nothing to say in this room just don't answer
+        if self.current_dialogue.is_empty() {
+            return format!("{} has nothing to say.", self.name);
+        }
         //if end of vec, return current index
-        let resp = self.current_dialogue[self.dialogue_counter].clone();
+        let resp = self.current_dialogue[self.dialogue_counter.min(self.current_dialogue.len() - 1)].clone();
         if self.dialogue_counter < self.current_dialogue.len() - 1 {
             self.dialogue_counter += 1;
         }
         resp.to_string()
     }
 
-    pub fn update_dialogue(&mut self, room: usize) {
-        self.current_dialogue = self.all_dialogues[&room].clone();
+    pub fn update_dialogue(&mut self, room: &RoomId) {
+        //npcs don't have to have lines for every room they end up in
+        self.current_dialogue = self.all_dialogues.get(room).cloned().unwrap_or_default();
         self.dialogue_counter = 0;
     }
 
-    pub fn move_to_room(&mut self, room: usize) {
-        self.current_room = room;
+    pub fn move_to_room(&mut self, room: &RoomId) {
+        self.current_room = room.clone();
         self.update_dialogue(room);
     }
+
+    // empties their inventory and loot table, for dropping on the floor when they're defeated
+    pub fn drop_everything(&mut self) -> Vec<Item> {
+        let mut items = std::mem::take(&mut self.inventory);
+        items.append(&mut self.loot);
+        items
+    }
+
+    // alive and standing in this room
+    pub fn is_in(&self, room: &RoomId) -> bool {
+        !self.dead && &self.current_room == room
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        World::new()
+    }
 }
 
 impl World {
     pub fn new() -> Self {
         World {
-            rooms: Vec::new(),
+            rooms: HashMap::new(),
             npcs: Vec::new(),
             flags: HashMap::new(),
+            start_room: RoomId::default(),
+            quests: Vec::new(),
+            triggers: Vec::new(),
+            skills: Vec::new(),
+            death: DeathRules::default(),
         }
     }
 
@@ -82,165 +264,140 @@ impl World {
             .unwrap_or(&false)
     }
 
-    // Creates and adds a room to the world, returning its index so we can map adjacent rooms to the world room vec
+    // the room with this id. for rooms we already know exist, like the one the player is in
+    pub fn room_mut(&mut self, id: &RoomId) -> &mut Room {
+        self.rooms.get_mut(id).expect("no room with that id in the world")
+    }
+
+    // the exit the player can see from this room in this direction. hidden exits don't count
+    pub fn visible_exit(&self, room: &RoomId, direction: &str) -> Option<&Exit> {
+        self.rooms
+            .get(room)
+            .and_then(|room| room.exits.get(direction))
+            .filter(|exit| !exit.hidden)
+    }
+
+    // The exit in this direction if it can be walked through right now, or why it can't
+    pub fn passable_exit(&self, room: &RoomId, direction: &str) -> Result<&Exit, MoveError> {
+        let exit = match self.visible_exit(room, direction) {
+            // an exit into a room that doesn't exist is a broken map, don't walk into the void
+            Some(exit) if self.rooms.contains_key(&exit.to) => exit,
+            _ => return Err(MoveError::NoExit),
+        };
+
+        if let Some(flag) = &exit.requires_flag {
+            if !self.get_flag(flag) {
+                return Err(MoveError::Blocked(exit.blocked_message.clone()));
+            }
+        }
+        match &exit.door {
+            Some(door) if door.locked => Err(MoveError::Locked),
+            Some(door) if door.closed => Err(MoveError::Closed),
+            _ => Ok(exit),
+        }
+    }
+
+    // directions the player can see from this room, sorted so they list the same way every time
+    pub fn visible_exits(&self, room: &RoomId) -> Vec<&String> {
+        let mut directions: Vec<_> = self.rooms[room]
+            .exits
+            .iter()
+            .filter(|(_, exit)| !exit.hidden)
+            .map(|(direction, _)| direction)
+            .collect();
+        directions.sort();
+        directions
+    }
+
+    // Changes the door on an exit. A door is one door from both sides, so if the room on the other
+    // side has a door leading back here it gets the same change.
+    pub fn update_door(&mut self, room: &RoomId, direction: &str, change: impl Fn(&mut Door)) {
+        let Some(exit) = self.rooms.get_mut(room).and_then(|r| r.exits.get_mut(direction)) else {
+            return;
+        };
+        let to = exit.to.clone();
+        if let Some(door) = &mut exit.door {
+            change(door);
+        }
+
+        if let Some(other_side) = self.rooms.get_mut(&to) {
+            for exit in other_side.exits.values_mut().filter(|e| &e.to == room) {
+                if let Some(door) = &mut exit.door {
+                    change(door);
+                }
+            }
+        }
+    }
+
+    // Creates and adds a room to the world, returning its id so exits can point at it
     pub fn create_room(
         &mut self,
+        id: &str,
         name: &str,
         description: &str,
         items: Vec<Item>,
-        exits: HashMap<String, usize>,
-    ) -> usize {
+        exits: HashMap<String, Exit>,
+    ) -> RoomId {
         let room = Room {
             name: name.to_string(),
             description: description.to_string(),
             items,
             exits,
+            position: None,
+            scenery: Vec::new(),
+            checkpoint: false,
         };
-        self.rooms.push(room);
-        self.rooms.len() - 1 // Return the index of the newly created room
+        let id = RoomId::from(id);
+        self.rooms.insert(id.clone(), room);
+        id
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_npc(
         &mut self,
+        id: &str,
         name: &str,
         current_dialogue: Vec<String>,
-        current_room: usize,
-        all_dialogues: HashMap<usize, Vec<String>>,
+        current_room: RoomId,
+        all_dialogues: HashMap<RoomId, Vec<String>>,
         hp: i32,
         ap: i32,
         hostility: bool
-    ) -> usize {
+    ) -> NpcId {
+        let id = NpcId::from(id);
         let npc = Npc {
+            id: id.clone(),
             name: name.to_string(),
+            description: String::new(),
             current_dialogue,
             current_room,
             all_dialogues,
             dialogue_counter: 0,
             health_points: hp,
+            max_health_points: hp,
             attack_power: ap,
-            hostile: hostility
-
+            hostile: hostility,
+            conversations: HashMap::new(),
+            dead: false,
+            xp_reward: 0,
+            inventory: Vec::new(),
+            loot: Vec::new(),
+            trades: Vec::new(),
         };
         self.npcs.push(npc);
-        self.npcs.len() - 1
-    }
-
-    /* INIT FN'S BELOW
-     * ++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++++
-     * ==================================================================================================================
-     */
-
-
-    /* create all world npcs and place them in rooms accordingly.
-     * Npcs, like players are not truly inside of a room, they are just have a current room they can look at.
-     * Monsters, creatures and bosses are all Npcs.
-     *
-     */
-    pub fn init_npcs(&mut self) {
-        //init npc dialogue first
-        let merlin_dialogues = HashMap::from([
-            (
-                1,
-                vec![
-                    "Greetings Traveler. I hope you found that key I left in the previous room.".to_string(),
-                    "You will find that you may need it here or there.".to_string(),
-                    "Good luck on your quest, perhaps we shall meet again soon.".to_string(),
-                ]
-            ),
-            (
-                3, //dialogue for room 3. make sure to move npc after skeleton is defeated
-                vec![
-                    "Hello again traveler.".to_string(),
-                    "Thanks for getting rid of that skeleton in the other room.".to_string(),
-                    "I must be going soon. Take care.".to_string()
-
-                ]
-            ),
-        ]);
-
-        //dialogue/quest npc
-        self.create_npc(
-            "Merlin",
-            merlin_dialogues[&1].clone(),
-            1,
-            merlin_dialogues,
-            20,
-            20,
-            false
-        );
-        self.create_npc("Skeleton", 
-        Vec::new(), 
-        2, 
-        HashMap::new(), 
-        50, 
-        10, 
-        true);
-    }
-    /*
-     * Initializes all the rooms.
-     * Indices must be planned ahead of time. It would help if you drew a map and numbered them.
-     */
-    pub fn init_rooms(&mut self) {
-        // Example rooms for testing, all rooms for your game need to be placed in here.
-        let room1 = self.create_room(
-            "Starting Room",
-            "You are in the starting room. There's an exit to the north.",
-            vec![Item {
-                name: "Key".to_string(),
-                description: "A small rusty key.".to_string(),
-                can_take: true,
-                can_eat: true,
-                can_equip: false,
-                heal_amount: -10,
-                attack_increase_amount: 0
-            }],
-            HashMap::from([("north".to_string(), 1)]),
-        );
-
-        let room2 = self.create_room(
-            "Maze Entrance",
-            "You stand at the entrance of a dark maze. Exits lead in all directions.",
-            vec![Item {
-                name: "Broken_Sword".to_string(),
-                description: "Not much use unless you're out of options.".to_string(),
-                can_take: true,
-                can_eat: false,
-                can_equip: true,
-                heal_amount: 0,
-                attack_increase_amount: 10
-                },
-                Item {
-                    name: "Healing_Grass".to_string(),
-                    description: "Heals a small amount of health.".to_string(),
-                    can_take: true,
-                    can_eat: true,
-                    can_equip: false,
-                    heal_amount: 20,
-                    attack_increase_amount: 0
-                }
-            ],
-            HashMap::from([
-                ("south".to_string(), room1), //either strategy here works
-                ("north".to_string(), 2), // but I prefer direct index mapping using a drawing of a world map
-                ("east".to_string(), 3),
-                ("stairs".to_string(), 4),
-            ]),
-        );
-
-        self.create_room(
-            "Maze Room 1",
-            "The walls here are identical, and you feel disoriented.",
-            vec![],
-            HashMap::from([("south".to_string(), room2)]),
-        );
-
-        self.create_room(
-            "Maze Room 2",
-            "A narrow corridor with a faint breeze.",
-            vec![],
-            HashMap::from([("west".to_string(), room2)]),
-        );
-
-        
+        id
+    }
+
+    pub fn npc(&self, id: &NpcId) -> Option<&Npc> {
+        self.npcs.iter().find(|n| &n.id == id)
+    }
+
+    pub fn npc_mut(&mut self, id: &NpcId) -> Option<&mut Npc> {
+        self.npcs.iter_mut().find(|n| &n.id == id)
+    }
+
+    // living npcs in a room, in the order they were created
+    pub fn npcs_in<'a>(&'a self, room: &'a RoomId) -> impl Iterator<Item = &'a Npc> + 'a {
+        self.npcs.iter().filter(move |n| n.is_in(room))
     }
 }
@@ -1,54 +1,116 @@
-mod game;
+use cyber_dungeon::game::{
+    commands::{self, process_command},
+    io::{GameIo, TerminalIo},
+    session::Session,
+    world::World,
+};
+use std::{
+    env, process,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-use game::{commands::process_command, player::Player, world::World};
-use std::io::{self, Write};
+// world used when no path is given on the command line, e.g. plain `cargo run`
+const DEFAULT_WORLD: &str = "worlds/cyber_dungeon.toml";
+
+// command line is: cyber-dungeon [path/to/world.toml] [--seed <number>]
+struct Args {
+    world_path: String,
+    seed: Option<u64>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut world_path = None;
+    let mut seed = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--seed" {
+            let value = args.next().ok_or("--seed needs a number")?;
+            seed = Some(value.parse().map_err(|_| format!("'{}' is not a valid seed", value))?);
+        } else if world_path.is_none() {
+            world_path = Some(arg);
+        } else {
+            return Err(format!("unexpected argument '{}'", arg));
+        }
+    }
+    Ok(Args {
+        world_path: world_path.unwrap_or_else(|| DEFAULT_WORLD.to_string()),
+        seed,
+    })
+}
 
 fn main() {
-    // Initialize the game world
-    let mut world = World::new();
-    world.init_rooms();
-    world.init_npcs();
+    let args = parse_args().unwrap_or_else(|err| {
+        eprintln!("{}\nusage: cyber-dungeon [path/to/world.toml] [--seed <number>]", err);
+        process::exit(2);
+    });
+
+    // Load the game world from a data file
+    let world_path = args.world_path;
+    let world = match World::from_file(&world_path) {
+        Ok(world) => world,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    // catch broken maps (exits to nowhere, unreachable rooms...) before anyone starts playing
+    let problems = world.validate();
+    if !problems.is_empty() {
+        eprintln!("{} has problems:", world_path);
+        for problem in &problems {
+            eprintln!("  - {}", problem);
+        }
+        process::exit(1);
+    }
 
-    //init the player
-    let mut player = Player::new(0); // Start in the first room, but you can start wherever you want I guess
-    let mut player_name = String::new();
+    //init the player in whatever room the world file says
+    // no seed given means a different game every time, the seed gets printed so it can be replayed
+    let seed = args.seed.unwrap_or_else(|| {
+        SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or_default()
+    });
+    let mut session = Session::new(world, seed);
+    let mut io = TerminalIo;
+    // every built-in command. a game built on the engine would register its own verbs here, see registry.rs
+    let commands = commands::builtins();
 
-    print!("\x1B[2J"); //clear window???? from stack overflow lol
+    io.write("\x1B[2J"); //clear window???? from stack overflow lol
 
     // this is more for my dungeon theme, can be whatever
-    print!("Username: ");
-    io::stdout().flush().unwrap();
-    io::stdin().read_line(&mut player_name).unwrap();
-    player_name = player_name.trim().to_string(); //fix broken prompt
+    let player_name = io.prompt("Username: ").unwrap_or_default();
+    let player_name = player_name.trim(); //fix broken prompt
 
     // this is more for my dungeon theme, can be whatever you want or none of it
-    println!("User {} logged in successfully.", player_name);
-    println!("Welcome to the Cyber Dungeon!");
-    println!("Type 'look' to see your surroundings, 'go <direction>' to move, or 'inventory' to check your items.");
+    io.write_line(&format!("User {} logged in successfully.", player_name));
+    io.write_line("Welcome to the Cyber Dungeon!");
+    io.write_line("Type 'look' to see your surroundings, 'go <direction>' to move, or 'help' to see everything you can do.");
+    io.write_line(&format!("(game seed {}, play the same game again with --seed {})", seed, seed));
 
     //main control loop: read input -> process command -> respond appropriately. see process_command fn for details.
     loop {
-        print!("{}@C-D > ", player_name);
-        io::stdout().flush().unwrap();
-
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
+        // no more input (ctrl-d) is treated the same as quit
+        let input = io.prompt(&format!("{}@C-D > ", player_name));
 
         //For Cli version only. not sure how this would work in wasm build
-        if input.trim().eq_ignore_ascii_case("quit") {
+        if input
+            .as_deref()
+            .is_none_or(|i| i.trim().eq_ignore_ascii_case("quit"))
+        {
             // maybe better as a match for quit, exit, logout, q, also
-            println!("Goodbye!");
-            println!("User {} logged out successfully.", player_name);
+            io.write_line("Goodbye!");
+            io.write_line(&format!("User {} logged out successfully.", player_name));
             break;
         }
+        let input = input.unwrap_or_default();
 
-        //flag checker function here
-        //maybe set a bool for each room 'player visited'
-        //check each room flag, custom logic for event triggers, npc movement, etc
+        //event triggers and quest progress are checked inside process_command after every command
+        let response = process_command(&input, &mut session, &commands);
+        io.write_line(&format!("\n{}\n", response));
 
-        let response = process_command(&input, &mut player, &mut world);
-        println!();
-        println!("{}", response);
-        println!();
+        // permadeath, nothing left to play
+        if session.is_over() {
+            io.write_line(&format!("User {} logged out.", player_name));
+            break;
+        }
     }
 }
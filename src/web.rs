@@ -0,0 +1,133 @@
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::game::{
+    commands::{self, process_command},
+    registry::{Command, CommandRegistry},
+    save,
+    session::{Mode, Session},
+    world::World,
+};
+
+/* BROWSER API
+ * A handle a static html page can hold on to and feed commands into, built with wasm-pack:
+ *
+ *   wasm-pack build --target web -- --no-default-features
+ *
+ *   const game = new Game(worldToml);
+ *   output.textContent += game.submit("look");
+ *   localStorage.setItem("save", game.save());
+ *
+ * Everything goes through process_command same as the terminal version, so nothing here
+ * needs to know about individual commands.
+ */
+#[wasm_bindgen(js_name = Game)]
+pub struct WebGame {
+    session: Session,
+    commands: CommandRegistry,
+}
+
+// what state_json hands to the page, just the stuff worth drawing on screen
+#[derive(Serialize)]
+struct GameView<'a> {
+    mode: &'a str,
+    room: &'a str,
+    description: &'a str,
+    exits: Vec<&'a String>,
+    items: Vec<&'a String>,
+    npcs: Vec<&'a String>,
+    health_points: i32,
+    attack_power: i32,
+    defense: i32,
+    level: u32,
+    xp: u32,
+    inventory: Vec<&'a String>,
+    equipment: Vec<&'a String>,
+}
+
+#[wasm_bindgen(js_class = Game)]
+impl WebGame {
+    // world_data is the contents of a world file (see game::loader). wasm has no clock to seed
+    // from, so the page passes a seed in (leave it out and every game rolls the same)
+    #[wasm_bindgen(constructor)]
+    pub fn new(world_data: &str, seed: Option<u32>) -> Result<WebGame, JsError> {
+        let world = World::from_str(world_data).map_err(|e| JsError::new(&e.to_string()))?;
+        let problems = world.validate();
+        if !problems.is_empty() {
+            let problems: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+            return Err(JsError::new(&format!("invalid world: {}", problems.join("; "))));
+        }
+        // slots are files in the saves folder, which the browser doesn't have. the page saves through
+        // save()/load() when the player types a bare "save" or "load", so these just point there
+        let mut commands = commands::builtins();
+        commands.register(
+            Command::new("save", "save", "Save the game in this browser.", |_, _| {
+                "Saves are kept in the browser, there are no slots. Just type 'save'.".to_string()
+            })
+            .anytime(),
+        );
+        commands.register(
+            Command::new("load", "load", "Load the game saved in this browser.", |_, _| {
+                "Saves are kept in the browser, there are no slots. Just type 'load'.".to_string()
+            })
+            .anytime(),
+        );
+        Ok(WebGame {
+            session: Session::new(world, seed.unwrap_or_default() as u64),
+            commands,
+        })
+    }
+
+    // runs one line of player input and returns the response text
+    pub fn submit(&mut self, command: &str) -> String {
+        process_command(command, &mut self.session, &self.commands)
+    }
+
+    #[wasm_bindgen(js_name = stateJson)]
+    pub fn state_json(&self) -> String {
+        let player = &self.session.player;
+        let world = &self.session.world;
+        let room = &world.rooms[&player.current_room];
+
+        let view = GameView {
+            mode: match self.session.mode {
+                Mode::Explore => "explore",
+                Mode::ConfirmAttack(_) => "confirm",
+                Mode::Combat(_) => "combat",
+                Mode::Dialogue(_) => "dialogue",
+                Mode::Choosing { .. } => "choosing",
+                Mode::GameOver => "game_over",
+            },
+            room: &room.name,
+            description: &room.description,
+            exits: world.visible_exits(&player.current_room),
+            items: room.items.iter().map(|i| &i.name).collect(),
+            npcs: world.npcs_in(&player.current_room).map(|n| &n.name).collect(),
+            health_points: player.health_points,
+            attack_power: player.attack(),
+            defense: player.defense(),
+            level: player.level,
+            xp: player.xp,
+            inventory: player.inventory.iter().map(|i| &i.name).collect(),
+            equipment: player.equipment.values().map(|i| &i.name).collect(),
+        };
+        serde_json::to_string(&view).unwrap_or_default()
+    }
+
+    // the whole game as a save string, store it wherever (localStorage, a download...)
+    pub fn save(&mut self) -> Result<String, JsError> {
+        let data = save::to_string(&self.session).map_err(|e| JsError::new(&e.to_string()))?;
+        self.session.last_save = Some(data.clone()); // where reloading on death goes back to
+        Ok(data)
+    }
+
+    // replaces the current game with one from save()
+    pub fn load(&mut self, data: &str) -> Result<(), JsError> {
+        let saved = save::from_str(data).map_err(|e| JsError::new(&e.to_string()))?;
+        let fresh_world = self.session.fresh_world.take();
+        self.session = saved;
+        self.session.fresh_world = fresh_world;
+        self.session.last_save = Some(data.to_string());
+        Ok(())
+    }
+}